@@ -29,3 +29,31 @@ Feature: Pagination Canonicals
     Then I should see a selector 'meta' in "output/page/2/index.html" with the attributes:
       | content  | https://example.com/blog/page/2/ |
       | property | og:url                           |
+
+  Scenario: If I ask for a canonical view all page, every page should point to it
+    Given I have a "source/index.html" file with the content:
+      """
+      <html>
+      <head>
+      <link rel="canonical" href="https://example.com/blog/" />
+      </head>
+      <body>
+      <section data-pagebreak="1" data-pagebreak-canonical="all">
+      <p>Item 1</p>
+      <p>Item 2</p>
+      </section>
+      </body>
+      </html>
+      """
+    When I run Pagebreak
+    Then I should see "Item 2" in "output/all/index.html"
+    And I should see a selector 'link' in "output/index.html" with the attributes:
+      | href     | https://example.com/blog/all/ |
+      | rel      | canonical                     |
+    And I should see a selector 'link' in "output/page/2/index.html" with the attributes:
+      | href     | https://example.com/blog/all/ |
+      | rel      | canonical                     |
+    And I should see a selector 'link' in "output/all/index.html" with the attributes:
+      | href     | https://example.com/blog/all/ |
+      | rel      | canonical                     |
+    But I should not see "data-pagebreak-canonical" in "output/index.html"
@@ -0,0 +1,207 @@
+Feature: Pagination Containers
+
+  Scenario: If I have multiple containers, each should be paginated on its own
+    Given I have a "source/index.html" file with the body:
+      """
+      <section id="posts" data-pagebreak="1">
+        <p>Post 1</p>
+        <p>Post 2</p>
+      </section>
+      <section id="events" data-pagebreak="2" data-pagebreak-url="./events/:num/">
+        <p>Event 1</p>
+        <p>Event 2</p>
+        <p>Event 3</p>
+      </section>
+      """
+    When I run Pagebreak
+    Then I should see "Post 1" in "output/index.html"
+    And I should see "Event 1" in "output/index.html"
+    And I should see "Post 2" in "output/page/2/index.html"
+    And I should see "Event 1" in "output/page/2/index.html"
+    And I should see "Post 1" in "output/events/2/index.html"
+    And I should see "Event 3" in "output/events/2/index.html"
+    But I should not see "Post 2" in "output/index.html"
+    And I should not see "Event 3" in "output/index.html"
+    And I should not see "Event 3" in "output/page/2/index.html"
+    And I should not see "Post 2" in "output/events/2/index.html"
+
+  Scenario: If I bind controls to a container, they should link within that container's pages
+    Given I have a "source/index.html" file with the body:
+      """
+      <section id="posts" data-pagebreak="1"><p></p><p></p></section>
+      <section id="events" data-pagebreak="1" data-pagebreak-url="./events/:num/"><p></p><p></p></section>
+      <a href="" data-pagebreak-control="next">Next Posts</a>
+      <a href="" data-pagebreak-control="next" data-pagebreak-for="events">Next Events</a>
+      <a href="" data-pagebreak-control="prev" data-pagebreak-for="events">Previous Events</a>
+      """
+    When I run Pagebreak
+    Then I should see a selector 'a' in "output/index.html" with the attributes:
+      | href      | page/2/    |
+      | innerText | Next Posts |
+    And I should see a selector 'a' in "output/index.html" with the attributes:
+      | href      | events/2/   |
+      | innerText | Next Events |
+    And I should see a selector 'a' in "output/page/2/index.html" with the attributes:
+      | href      | ../../events/2/ |
+      | innerText | Next Events     |
+    And I should see a selector 'a' in "output/events/2/index.html" with the attributes:
+      | href      | ../../          |
+      | innerText | Previous Events |
+    And I should see a selector 'a' in "output/events/2/index.html" with the attributes:
+      | href      | ../../page/2/ |
+      | innerText | Next Posts    |
+    But I should not see "Previous Events" in "output/index.html"
+    And I should not see "Next Events" in "output/events/2/index.html"
+    And I should not see "data-pagebreak-for" in "output/index.html"
+
+  Scenario: If my containers share a pagination URL, only the first should be paginated
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="1"><p>Post 1</p><p>Post 2</p></section>
+      <section data-pagebreak="1"><p>Event 1</p><p>Event 2</p></section>
+      """
+    When I run Pagebreak
+    Then I should see "Event 2" in "output/index.html"
+    And I should see "Post 2" in "output/page/2/index.html"
+    But I should not see "Post 2" in "output/index.html"
+
+  Scenario: If I group containers, they should share one set of pages
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="1" data-pagebreak-group="listing">
+        <p>Hero 1</p>
+        <p>Hero 2</p>
+      </section>
+      <section data-pagebreak="2" data-pagebreak-group="listing">
+        <p>Card 1</p>
+        <p>Card 2</p>
+        <p>Card 3</p>
+        <p>Card 4</p>
+        <p>Card 5</p>
+      </section>
+      <a href="" data-pagebreak-control="next">Next Page</a>
+      <p>Total <span data-pagebreak-label="total">1</span></p>
+      """
+    When I run Pagebreak
+    Then I should see "Hero 1" in "output/index.html"
+    And I should see "Card 2" in "output/index.html"
+    And I should see "Hero 2" in "output/page/2/index.html"
+    And I should see "Card 4" in "output/page/2/index.html"
+    And I should see "Card 5" in "output/page/3/index.html"
+    And I should see '<p>Total <span>3</span></p>' in "output/index.html"
+    And I should see a selector 'a' in "output/page/2/index.html" with the attributes:
+      | href      | ../3/     |
+      | innerText | Next Page |
+    But I should not see "Hero 2" in "output/index.html"
+    And I should not see "Card 3" in "output/index.html"
+    And I should not see "Hero" in "output/page/3/index.html"
+    And I should not see "data-pagebreak-group" in "output/index.html"
+
+  Scenario: If I nest containers, each nested container should be paginated under its parent's page
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="1" data-pagebreak-url="./category/:num/">
+        <div>
+          <h2>Category A</h2>
+          <ul data-pagebreak="1">
+            <li>Post A1</li>
+            <li>Post A2</li>
+          </ul>
+          <a href="" data-pagebreak-control="next">More A</a>
+        </div>
+        <div>
+          <h2>Category B</h2>
+          <ul data-pagebreak="1">
+            <li>Post B1</li>
+            <li>Post B2</li>
+            <li>Post B3</li>
+          </ul>
+          <a href="" data-pagebreak-control="next">More B</a>
+        </div>
+      </section>
+      <a href="" data-pagebreak-control="next">Next Category</a>
+      """
+    When I run Pagebreak
+    Then I should see "Post A1" in "output/index.html"
+    And I should see "Post A2" in "output/page/2/index.html"
+    And I should see "Post B1" in "output/category/2/index.html"
+    And I should see "Post B3" in "output/category/2/page/3/index.html"
+    And I should see a selector 'a' in "output/category/2/page/2/index.html" with the attributes:
+      | href      | ../3/  |
+      | innerText | More B |
+    And I should see a selector 'a' in "output/page/2/index.html" with the attributes:
+      | href      | ../../category/2/ |
+      | innerText | Next Category     |
+    But I should not see "Post A2" in "output/index.html"
+    And I should not see "Post B2" in "output/category/2/index.html"
+    And I should not see "data-pagebreak" in "output/category/2/page/2/index.html"
+
+  Scenario: If two nested containers share a page, each should be paginated under its own URL
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="2" data-pagebreak-url="./category/:num/">
+        <div>
+          <ul data-pagebreak="1" data-pagebreak-url="./a/:num/">
+            <li>Post A1</li>
+            <li>Post A2</li>
+          </ul>
+        </div>
+        <div>
+          <ul data-pagebreak="1" data-pagebreak-url="./b/:num/">
+            <li>Post B1</li>
+            <li>Post B2</li>
+          </ul>
+        </div>
+      </section>
+      """
+    When I run Pagebreak
+    Then I should see "Post A2" in "output/a/2/index.html"
+    And I should see "Post B1" in "output/a/2/index.html"
+    And I should see "Post B2" in "output/b/2/index.html"
+    And I should see "Post A1" in "output/b/2/index.html"
+    But I should not see "Post B2" in "output/a/2/index.html"
+
+  Scenario: If two nested containers on a page share a URL, the second should not overwrite the first
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="2" data-pagebreak-url="./category/:num/">
+        <div>
+          <ul data-pagebreak="1" data-pagebreak-url="./more/:num/">
+            <li>Post A1</li>
+            <li>Post A2</li>
+          </ul>
+        </div>
+        <div>
+          <ul data-pagebreak="1" data-pagebreak-url="./more/:num/">
+            <li>Post B1</li>
+            <li>Post B2</li>
+          </ul>
+        </div>
+      </section>
+      """
+    When I run Pagebreak
+    Then I should see "Post A2" in "output/more/2/index.html"
+    But I should not see "Post B2" in "output/more/2/index.html"
+
+  Scenario: If a nested container shares its parent's URL, it should not overwrite its parent's pages
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="1">
+        <div>
+          <ul data-pagebreak="1">
+            <li>Post A1</li>
+            <li>Post A2</li>
+          </ul>
+        </div>
+        <div>
+          <ul data-pagebreak="1">
+            <li>Post B1</li>
+            <li>Post B2</li>
+          </ul>
+        </div>
+      </section>
+      """
+    When I run Pagebreak
+    Then I should see "Post B1" in "output/page/2/index.html"
+    And I should see "Post B2" in "output/page/2/page/2/index.html"
+    But I should not see "Post A2" in "output/page/2/index.html"
@@ -88,6 +88,40 @@ Feature: Pagination Controls
     But I should not see "No Next" in "output/index.html"
     And I should not see "No Previous" in "output/page/2/index.html"
 
+  Scenario: If I have first and last controls, they should link to the ends of my pages
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="1"><p></p><p></p><p></p></section>
+      <section>
+      <a href="" data-pagebreak-control="first">First Page</a>
+      <span data-pagebreak-control="!first">No First</span>
+      <a href="" data-pagebreak-control="last">Last Page</a>
+      <span data-pagebreak-control="!last">No Last</span>
+      </section>
+      """
+    When I run Pagebreak
+    Then I should see a selector 'a' in "output/index.html" with the attributes:
+      | href      | page/3/   |
+      | innerText | Last Page |
+    And I should see a selector 'span' in "output/index.html" with the attributes:
+      | innerText | No First |
+    And I should see a selector 'a' in "output/page/2/index.html" with the attributes:
+      | href      | ../../     |
+      | innerText | First Page |
+    And I should see a selector 'a' in "output/page/2/index.html" with the attributes:
+      | href      | ../3/     |
+      | innerText | Last Page |
+    And I should see a selector 'a' in "output/page/3/index.html" with the attributes:
+      | href      | ../../     |
+      | innerText | First Page |
+    And I should see a selector 'span' in "output/page/3/index.html" with the attributes:
+      | innerText | No Last |
+    But I should not see "First Page" in "output/index.html"
+    And I should not see "No Last" in "output/index.html"
+    And I should not see "No First" in "output/page/2/index.html"
+    And I should not see "No Last" in "output/page/2/index.html"
+    And I should not see "Last Page" in "output/page/3/index.html"
+
   # Prevent regression
   Scenario: Pagination controls with no href should work
     Given I have a "source/index.html" file with the body:
@@ -110,4 +144,126 @@ Feature: Pagination Controls
       | innerText | Previous Page |
     Then I should see a selector 'a' in "output/page/3/index.html" with the attributes:
       | href      | ../2/         |
-      | innerText | Previous Page |
\ No newline at end of file
+      | innerText | Previous Page |
+
+  Scenario: If I have a page list control, it should be repeated for each page
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="1"><p></p><p></p><p></p></section>
+      <nav>
+      <a href="" data-pagebreak-control="pages">1</a>
+      </nav>
+      """
+    When I run Pagebreak
+    Then I should see a selector 'a' in "output/index.html" with the attributes:
+      | href         | ./   |
+      | aria-current | page |
+      | innerText    | 1    |
+    And I should see a selector 'a' in "output/index.html" with the attributes:
+      | href      | page/2/ |
+      | innerText | 2       |
+    And I should see a selector 'a' in "output/index.html" with the attributes:
+      | href      | page/3/ |
+      | innerText | 3       |
+    And I should see a selector 'a' in "output/page/2/index.html" with the attributes:
+      | href      | ../../ |
+      | innerText | 1      |
+    And I should see a selector 'a' in "output/page/2/index.html" with the attributes:
+      | href         | ./   |
+      | aria-current | page |
+      | innerText    | 2    |
+    And I should not see "data-pagebreak-control" in "output/index.html"
+
+  Scenario: If I have many pages, my page list should skip distant pages with an ellipsis
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="1"><p></p><p></p><p></p><p></p><p></p><p></p><p></p><p></p></section>
+      <nav>
+      <a href="" data-pagebreak-control="pages" data-pagebreak-window="1">1</a>
+      <span data-pagebreak-control="ellipsis">Skipped</span>
+      </nav>
+      """
+    When I run Pagebreak
+    Then I should see a selector 'a' in "output/page/5/index.html" with the attributes:
+      | href      | ../4/ |
+      | innerText | 4     |
+    And I should see a selector 'a' in "output/page/5/index.html" with the attributes:
+      | href      | ../6/ |
+      | innerText | 6     |
+    And I should see a selector 'a' in "output/page/5/index.html" with the attributes:
+      | href      | ../8/ |
+      | innerText | 8     |
+    And I should see "<span>Skipped</span>" in "output/page/5/index.html"
+    But I should not see "../3/" in "output/page/5/index.html"
+    And I should not see "data-pagebreak-window" in "output/page/5/index.html"
+
+  Scenario: If I have a tag list control, it should link to each tag listing
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="1">
+        <p data-pagebreak-tags="Rust">Item 1</p>
+        <p data-pagebreak-tags="Web Dev">Item 2</p>
+      </section>
+      <nav>
+      <a href="" data-pagebreak-control="tags">Tag</a>
+      </nav>
+      """
+    When I run Pagebreak
+    Then I should see a selector 'a' in "output/index.html" with the attributes:
+      | href      | tag/rust/ |
+      | innerText | Rust      |
+    And I should see a selector 'a' in "output/index.html" with the attributes:
+      | href      | tag/web-dev/ |
+      | innerText | Web Dev      |
+    And I should see a selector 'a' in "output/page/2/index.html" with the attributes:
+      | href      | ../../tag/rust/ |
+      | innerText | Rust            |
+    And I should see a selector 'a' in "output/tag/rust/index.html" with the attributes:
+      | href         | ./   |
+      | aria-current | page |
+      | innerText    | Rust |
+    And I should see a selector 'a' in "output/tag/rust/index.html" with the attributes:
+      | href      | ../web-dev/ |
+      | innerText | Web Dev     |
+    And I should not see "data-pagebreak-control" in "output/tag/rust/index.html"
+
+  Scenario: If I cap the number of pages, the final page should show the overflow control
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="1" data-pagebreak-max-pages="2">
+        <p>Item 1</p>
+        <p>Item 2</p>
+        <p>Item 3</p>
+      </section>
+      <a href="/search/" data-pagebreak-control="overflow">Search all items</a>
+      """
+    When I run Pagebreak
+    Then I should see "Item 2" in "output/page/2/index.html"
+    And I should see a selector 'a' in "output/page/2/index.html" with the attributes:
+      | href      | /search/         |
+      | innerText | Search all items |
+    But I should not see the file "output/page/3/index.html"
+    And I should not see "Item 3" in "output/page/2/index.html"
+    And I should not see "Search all items" in "output/index.html"
+    And I should not see "data-pagebreak-control" in "output/page/2/index.html"
+
+  Scenario: If I write a view all page, the all control should link to it
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="1" data-pagebreak-all="./everything/">
+        <p>Item 1</p>
+        <p>Item 2</p>
+      </section>
+      <a href="#" data-pagebreak-control="all">View all</a>
+      """
+    When I run Pagebreak
+    Then I should see "Item 1" in "output/everything/index.html"
+    And I should see "Item 2" in "output/everything/index.html"
+    And I should see a selector 'a' in "output/index.html" with the attributes:
+      | href      | everything/ |
+      | innerText | View all    |
+    And I should see a selector 'a' in "output/page/2/index.html" with the attributes:
+      | href      | ../../everything/ |
+      | innerText | View all          |
+    But I should not see "data-pagebreak-all" in "output/index.html"
+    And I should not see "View all" in "output/everything/index.html"
@@ -11,3 +11,56 @@ Feature: Pagination Labels
 		When I run Pagebreak
 		Then I should see '<p>Page <span>1</span> of <span>2</span></p>' in "output/index.html"
 		And I should see '<p>Page <span>2</span> of <span>2</span></p>' in "output/page/2/index.html"
+
+	Scenario: If I have item labels, they should reflect the items shown on each page
+		Given I have a "source/index.html" file with the body:
+			"""
+			<section data-pagebreak="2"><p></p><p></p><p></p><p></p><p></p></section>
+			<section>
+			<p>Showing <span data-pagebreak-label="first-item">1</span>-<span data-pagebreak-label="last-item">1</span> of <span data-pagebreak-label="total-items">1</span></p>
+			</section>
+			"""
+		When I run Pagebreak
+		Then I should see '<p>Showing <span>1</span>-<span>2</span> of <span>5</span></p>' in "output/index.html"
+		And I should see '<p>Showing <span>3</span>-<span>4</span> of <span>5</span></p>' in "output/page/2/index.html"
+		And I should see '<p>Showing <span>5</span>-<span>5</span> of <span>5</span></p>' in "output/page/3/index.html"
+
+	Scenario: If I have a formatted label, it should be filled in for each page
+		Given I have a "source/index.html" file with the body:
+			"""
+			<section data-pagebreak="2"><p></p><p></p><p></p></section>
+			<section>
+			<p data-pagebreak-label="Page :num of :total (:items items)"></p>
+			<p data-pagebreak-label="1 page|:total pages"></p>
+			</section>
+			"""
+		When I run Pagebreak
+		Then I should see '<p>Page 1 of 2 (3 items)</p>' in "output/index.html"
+		And I should see '<p>Page 2 of 2 (3 items)</p>' in "output/page/2/index.html"
+		And I should see '<p>2 pages</p>' in "output/index.html"
+		But I should not see "data-pagebreak-label" in "output/index.html"
+
+	Scenario: If I have a pluralised label, it should pick the form matching its count
+		Given I have a "source/index.html" file with the body:
+			"""
+			<section data-pagebreak="5"><p></p></section>
+			<section>
+			<p data-pagebreak-label="1 page|:total pages"></p>
+			<p data-pagebreak-label="No items|One item|:items items"></p>
+			</section>
+			"""
+		When I run Pagebreak
+		Then I should see '<p>1 page</p>' in "output/index.html"
+		And I should see '<p>One item</p>' in "output/index.html"
+
+	Scenario: If I have a label with an escaped separator, it should keep the separator as text
+		Given I have a "source/index.html" file with the body:
+			"""
+			<section data-pagebreak="1"><p>1</p><p>2</p></section>
+			<section>
+			<p data-pagebreak-label="Posts || Page :num of :total"></p>
+			</section>
+			"""
+		When I run Pagebreak
+		Then I should see '<p>Posts | Page 1 of 2</p>' in "output/index.html"
+		And I should see '<p>Posts | Page 2 of 2</p>' in "output/page/2/index.html"
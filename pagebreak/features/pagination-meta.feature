@@ -115,3 +115,24 @@ Feature: Pagination Meta
       | property  | twitter:title |
     And I should see a selector 'title' in "output/page/2/index.html" with the attributes:
       | innerText | Website Title |
+
+  Scenario: If I write a view all page, its title should not be numbered
+    Given I have a "source/index.html" file with the content:
+      """
+      <html>
+      <head>
+      <title>Website Title</title>
+      </head>
+      <body>
+      <section data-pagebreak="1" data-pagebreak-all>
+      <p>Item 1</p>
+      <p>Item 2</p>
+      </section>
+      </body>
+      </html>
+      """
+    When I run Pagebreak
+    Then I should see a selector 'title' in "output/all/index.html" with the attributes:
+      | innerText | Website Title              |
+    And I should see a selector 'title' in "output/page/2/index.html" with the attributes:
+      | innerText | Website Title \PIPE Page 2 |
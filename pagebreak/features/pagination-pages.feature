@@ -45,3 +45,378 @@ Feature: Pagination Pages
     Then I should see the file "output/page/3/index.html"
     And I should see "Item 5" in "output/page/3/index.html"
     But I should not see "Item 4" in "output/page/3/index.html"
+
+  Scenario: If I set a first page size, my first page should hold a different number of items
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="2" data-pagebreak-first="1">
+        <p>Item 1</p>
+        <p>Item 2</p>
+        <p>Item 3</p>
+        <p>Item 4</p>
+      </section>
+      <p>Showing <span data-pagebreak-label="first-item"></span>-<span data-pagebreak-label="last-item"></span></p>
+      """
+    When I run Pagebreak
+    Then I should see "Item 1" in "output/index.html"
+    And I should see "Item 2" in "output/page/2/index.html"
+    And I should see "Item 3" in "output/page/2/index.html"
+    And I should see "Item 4" in "output/page/3/index.html"
+    And I should see "<p>Showing <span>2</span>-<span>3</span></p>" in "output/page/2/index.html"
+    But I should not see "Item 2" in "output/index.html"
+    And I should not see the file "output/page/4/index.html"
+
+  Scenario: If my items have keys, a new page should start whenever the key changes
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="2">
+        <p data-pagebreak-key="2024">Item 1</p>
+        <p data-pagebreak-key="2024">Item 2</p>
+        <p data-pagebreak-key="2024">Item 3</p>
+        <p data-pagebreak-key="2023">Item 4</p>
+      </section>
+      """
+    When I run Pagebreak
+    Then I should see "Item 1" in "output/index.html"
+    And I should see "Item 2" in "output/index.html"
+    And I should see "Item 3" in "output/page/2/index.html"
+    And I should see "Item 4" in "output/page/3/index.html"
+    But I should not see "Item 4" in "output/page/2/index.html"
+    And I should not see "data-pagebreak-key" in "output/index.html"
+
+  Scenario: If my items have weights, pages should be filled up to the page size
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="3">
+        <p data-pagebreak-weight="2">Item 1</p>
+        <p>Item 2</p>
+        <p data-pagebreak-weight="2">Item 3</p>
+        <p>Item 4</p>
+        <p>Item 5</p>
+      </section>
+      """
+    When I run Pagebreak
+    Then I should see "Item 1" in "output/index.html"
+    And I should see "Item 2" in "output/index.html"
+    And I should see "Item 3" in "output/page/2/index.html"
+    And I should see "Item 4" in "output/page/2/index.html"
+    And I should see "Item 5" in "output/page/3/index.html"
+    But I should not see "Item 3" in "output/index.html"
+    And I should not see "data-pagebreak-weight" in "output/index.html"
+
+  Scenario: If I measure my items by words, pages should be filled up to a word count
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="5" data-pagebreak-measure="words">
+        <p>Item one two three</p>
+        <p>Item four</p>
+        <p>Item five six seven eight nine ten</p>
+      </section>
+      """
+    When I run Pagebreak
+    Then I should see "Item one" in "output/index.html"
+    And I should see "Item four" in "output/page/2/index.html"
+    And I should see "Item five" in "output/page/3/index.html"
+
+  Scenario: If my last page would hold too few items, they should join the page before it
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="2" data-pagebreak-orphans="2">
+        <p>Item 1</p>
+        <p>Item 2</p>
+        <p>Item 3</p>
+        <p>Item 4</p>
+        <p>Item 5</p>
+      </section>
+      """
+    When I run Pagebreak
+    Then I should see "Item 3" in "output/page/2/index.html"
+    And I should see "Item 4" in "output/page/2/index.html"
+    And I should see "Item 5" in "output/page/2/index.html"
+    But I should not see the file "output/page/3/index.html"
+    And I should not see "data-pagebreak-orphans" in "output/index.html"
+
+  Scenario: If I pin items, they should appear on every page
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="1">
+        <p data-pagebreak-pin="top">Announcement</p>
+        <p>Item 1</p>
+        <p>Item 2</p>
+        <p data-pagebreak-pin="bottom">Sponsor</p>
+      </section>
+      <p data-pagebreak-label="total-items"></p>
+      """
+    When I run Pagebreak
+    Then I should see "Announcement" in "output/index.html"
+    And I should see "Sponsor" in "output/index.html"
+    And I should see "Announcement" in "output/page/2/index.html"
+    And I should see "Item 2" in "output/page/2/index.html"
+    And I should see "Sponsor" in "output/page/2/index.html"
+    And I should see "<p>2</p>" in "output/index.html"
+    But I should not see "Item 2" in "output/index.html"
+    And I should not see the file "output/page/3/index.html"
+    And I should not see "data-pagebreak-pin" in "output/index.html"
+
+  Scenario: If I mark children as static, they should keep their place on every page
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="2">
+        <h2 data-pagebreak-static>Latest</h2>
+        <p>Item 1</p>
+        <p>Item 2</p>
+        <aside data-pagebreak-static>Ad</aside>
+        <p>Item 3</p>
+        <p>Item 4</p>
+      </section>
+      <p data-pagebreak-label="total-items"></p>
+      """
+    When I run Pagebreak
+    Then I should see a selector "section > h2:first-child + p + p + aside:last-child" in "output/index.html"
+    And I should see "Item 2" in "output/index.html"
+    And I should see a selector "section > h2:first-child + p + p + aside:last-child" in "output/page/2/index.html"
+    And I should see "Item 4" in "output/page/2/index.html"
+    And I should see "<p>4</p>" in "output/index.html"
+    But I should not see the file "output/page/3/index.html"
+    And I should not see "data-pagebreak-static" in "output/index.html"
+
+  Scenario: If I add an insert template, it should be repeated between items on each page
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="3">
+        <template data-pagebreak-insert-every="2">
+          <aside>Sign up</aside>
+        </template>
+        <p>Item 1</p>
+        <p>Item 2</p>
+        <p>Item 3</p>
+        <p>Item 4</p>
+      </section>
+      <p data-pagebreak-label="total-items"></p>
+      """
+    When I run Pagebreak
+    Then I should see a selector "section > p + p + aside + p:last-child" in "output/index.html"
+    And I should see "Item 3" in "output/index.html"
+    And I should see "Item 4" in "output/page/2/index.html"
+    And I should see "<p>4</p>" in "output/index.html"
+    But I should not see "Sign up" in "output/page/2/index.html"
+    And I should not see "template" in "output/index.html"
+
+  Scenario: If I sort my items, they should be paginated in that order
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="2" data-pagebreak-sort="data-date:desc">
+        <p data-date="2023-01-02">Item B</p>
+        <p data-date="2023-03-01">Item C</p>
+        <p data-date="2022-12-01">Item A</p>
+      </section>
+      """
+    When I run Pagebreak
+    Then I should see "Item C" in "output/index.html"
+    And I should see "Item B" in "output/index.html"
+    And I should see "Item A" in "output/page/2/index.html"
+    But I should not see "Item A" in "output/index.html"
+    And I should not see "data-pagebreak-sort" in "output/index.html"
+
+  Scenario: If I select items, they should be replaced in place within their wrappers
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="2" data-pagebreak-items=".card">
+        <h2>Cards</h2>
+        <div class="layout">
+          <ul>
+            <li class="card">Item 1</li>
+            <li class="card">Item 2</li>
+            <li class="card">Item 3</li>
+          </ul>
+        </div>
+      </section>
+      """
+    When I run Pagebreak
+    Then I should see "Item 2" in "output/index.html"
+    And I should see "Item 3" in "output/page/2/index.html"
+    And I should see a selector "section > h2 + .layout > ul > .card" in "output/page/2/index.html"
+    But I should not see "Item 3" in "output/index.html"
+    And I should not see "Item 1" in "output/page/2/index.html"
+    And I should not see the file "output/page/3/index.html"
+    And I should not see "data-pagebreak-items" in "output/index.html"
+
+  Scenario: If my items span several elements, they should be paginated together
+    Given I have a "source/index.html" file with the body:
+      """
+      <dl data-pagebreak="1" data-pagebreak-item-start="dt">
+        <dt>Term 1</dt>
+        <dd>Definition 1</dd>
+        <dt>Term 2</dt>
+        <dd>Definition 2</dd>
+        <dd>Definition 2b</dd>
+      </dl>
+      <p data-pagebreak-label="total-items"></p>
+      """
+    When I run Pagebreak
+    Then I should see "Definition 1" in "output/index.html"
+    And I should see "Term 2" in "output/page/2/index.html"
+    And I should see "Definition 2b" in "output/page/2/index.html"
+    And I should see "<p>2</p>" in "output/index.html"
+    But I should not see "Term 2" in "output/index.html"
+    And I should not see the file "output/page/3/index.html"
+    And I should not see "data-pagebreak-item-start" in "output/index.html"
+
+  Scenario: If I mark where my items start, the elements after each marker should stay with it
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="1">
+        <h3 data-pagebreak-item-start>Heading 1</h3>
+        <ul><li>List 1</li></ul>
+        <h3 data-pagebreak-item-start>Heading 2</h3>
+        <ul><li>List 2</li></ul>
+      </section>
+      """
+    When I run Pagebreak
+    Then I should see "List 1" in "output/index.html"
+    And I should see "Heading 2" in "output/page/2/index.html"
+    And I should see "List 2" in "output/page/2/index.html"
+    But I should not see "Heading 2" in "output/index.html"
+    And I should not see "data-pagebreak-item-start" in "output/page/2/index.html"
+
+  Scenario: If a heading's items continue onto the next page, the heading should be repeated
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="2">
+        <h2 data-pagebreak-heading=":content (continued)">March</h2>
+        <p>Item 1</p>
+        <p>Item 2</p>
+        <p>Item 3</p>
+        <h2 data-pagebreak-heading>April</h2>
+        <p>Item 4</p>
+      </section>
+      <p data-pagebreak-label="total-items"></p>
+      """
+    When I run Pagebreak
+    Then I should see "<h2>March</h2>" in "output/index.html"
+    And I should see "<h2>March (continued)</h2>" in "output/page/2/index.html"
+    And I should see "Item 3" in "output/page/2/index.html"
+    And I should see "<h2>April</h2>" in "output/page/2/index.html"
+    And I should see "<p>4</p>" in "output/index.html"
+    But I should not see "April" in "output/index.html"
+    And I should not see the file "output/page/3/index.html"
+    And I should not see "data-pagebreak-heading" in "output/page/2/index.html"
+
+  Scenario: If I place page break markers, pages should be split at each marker
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak>
+        <p>Item 1</p>
+        <p>Item 2</p>
+        <hr data-pagebreak-break>
+        <p>Item 3</p>
+      </section>
+      """
+    When I run Pagebreak
+    Then I should see "Item 2" in "output/index.html"
+    And I should see "Item 3" in "output/page/2/index.html"
+    But I should not see "Item 3" in "output/index.html"
+    And I should not see "<hr" in "output/index.html"
+    And I should not see the file "output/page/3/index.html"
+
+  Scenario: If I place page break markers with a page size, pages should still be split by size
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="2">
+        <p>Item 1</p>
+        <hr data-pagebreak-break>
+        <p>Item 2</p>
+        <p>Item 3</p>
+        <p>Item 4</p>
+      </section>
+      """
+    When I run Pagebreak
+    Then I should see "Item 1" in "output/index.html"
+    And I should see "Item 3" in "output/page/2/index.html"
+    And I should see "Item 4" in "output/page/3/index.html"
+    But I should not see "Item 2" in "output/index.html"
+    And I should not see "data-pagebreak-break" in "output/page/2/index.html"
+
+  Scenario: If I paginate an article, it should be split at each heading
+    Given I have a "source/index.html" file with the body:
+      """
+      <article data-pagebreak-article="h2">
+        <div class="prose">
+          <p>Introduction</p>
+          <h2>Setup</h2>
+          <p>Install it</p>
+          <h2>Usage</h2>
+          <p>Run it</p>
+        </div>
+      </article>
+      """
+    When I run Pagebreak
+    Then I should see "Introduction" in "output/index.html"
+    And I should see a selector "article > .prose > h2" in "output/page/2/index.html"
+    And I should see "Install it" in "output/page/2/index.html"
+    And I should see "Run it" in "output/page/3/index.html"
+    But I should not see "Setup" in "output/index.html"
+    And I should not see "Usage" in "output/page/2/index.html"
+    And I should not see "data-pagebreak-article" in "output/index.html"
+
+  Scenario: If I paginate an article, it should be split at each more marker
+    Given I have a "source/index.html" file with the body:
+      """
+      <article data-pagebreak-article>
+        <p>Part 1</p>
+        <!-- more -->
+        <p>Part 2</p>
+      </article>
+      """
+    When I run Pagebreak
+    Then I should see "Part 1" in "output/index.html"
+    And I should see "Part 2" in "output/page/2/index.html"
+    But I should not see "Part 2" in "output/index.html"
+    And I should not see "more" in "output/page/2/index.html"
+
+  Scenario: If I place controls within an article, they should be moved after it
+    Given I have a "source/index.html" file with the body:
+      """
+      <article data-pagebreak-article="h2">
+        <p>Introduction</p>
+        <a data-pagebreak-control="prev">Previous</a>
+        <h2>Setup</h2>
+        <p>Install it</p>
+        <a data-pagebreak-control="next">Next</a>
+      </article>
+      """
+    When I run Pagebreak
+    Then I should see "Introduction" in "output/index.html"
+    And I should see a selector "article + a" in "output/index.html" with the attributes:
+      | href      | page/2/ |
+      | innerText | Next    |
+    And I should see a selector "article + a" in "output/page/2/index.html" with the attributes:
+      | href      | ../../   |
+      | innerText | Previous |
+    But I should not see "Next" in "output/page/2/index.html"
+
+  Scenario: If a container has no items, it should still show its first page
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="2">
+        <p data-pagebreak-empty>No posts yet</p>
+      </section>
+      <span data-pagebreak-label="total">?</span>
+      """
+    When I run Pagebreak
+    Then I should see "No posts yet" in "output/index.html"
+    And I should see "<span>1</span>" in "output/index.html"
+    But I should not see "data-pagebreak-empty" in "output/index.html"
+    And I should not see the file "output/page/2/index.html"
+
+  Scenario: If a container has items, its empty placeholder should be removed
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="2">
+        <p data-pagebreak-empty>No posts yet</p>
+        <p>Item 1</p>
+      </section>
+      """
+    When I run Pagebreak
+    Then I should see "Item 1" in "output/index.html"
+    But I should not see "No posts yet" in "output/index.html"
@@ -62,3 +62,143 @@ Feature: Pagination URLs
     Then I should see the file "output/about.html"
     And I should see the file "output/about/page/2/index.html"
     But I should not see the file "output/about/page/2.html"
+
+  Scenario: If my items have keys, I should be able to use them in my URLs
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="10" data-pagebreak-url="./year/:key/">
+        <p data-pagebreak-key="2024">Item 1</p>
+        <p data-pagebreak-key="2023">Item 2</p>
+        <p data-pagebreak-key="2022">Item 3</p>
+      </section>
+      """
+    When I run Pagebreak
+    Then I should see "Item 1" in "output/index.html"
+    And I should see "Item 2" in "output/year/2023/index.html"
+    And I should see "Item 3" in "output/year/2022/index.html"
+
+  Scenario: If pages of one key share a URL, only the first should be written
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="2" data-pagebreak-url="./:key/">
+        <p data-pagebreak-key="2024">Item 1</p>
+        <p data-pagebreak-key="2024">Item 2</p>
+        <p data-pagebreak-key="2023">Item 3</p>
+        <p data-pagebreak-key="2023">Item 4</p>
+        <p data-pagebreak-key="2023">Item 5</p>
+      </section>
+      """
+    When I run Pagebreak
+    Then I should see "Item 3" in "output/2023/index.html"
+    And I should see "Item 4" in "output/2023/index.html"
+    But I should not see "Item 5" in "output/2023/index.html"
+
+  Scenario: If I add alternate sorts, each should be written as its own listing
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="2" data-pagebreak-sorts="by-title=text, by-price=data-price:desc">
+        <p data-price="5">Item B</p>
+        <p data-price="20">Item C</p>
+        <p data-price="10">Item A</p>
+      </section>
+      <a data-pagebreak-control="next">Next</a>
+      """
+    When I run Pagebreak
+    Then I should see "Item B" in "output/index.html"
+    And I should see "Item A" in "output/page/2/index.html"
+    And I should see "Item A" in "output/by-title/index.html"
+    And I should see "Item C" in "output/by-title/page/2/index.html"
+    And I should see 'href="page/2/"' in "output/by-title/index.html"
+    And I should see "Item C" in "output/by-price/index.html"
+    And I should see "Item A" in "output/by-price/index.html"
+    And I should see "Item B" in "output/by-price/page/2/index.html"
+    But I should not see "Item C" in "output/by-title/index.html"
+    And I should not see "data-pagebreak-sorts" in "output/by-title/index.html"
+
+  Scenario: If my items have tags, each tag should be written as its own listing
+    Given I have a "source/index.html" file with the body:
+      """
+      <h1 data-pagebreak-label="Posts tagged :tag">Posts</h1>
+      <section data-pagebreak="1">
+        <p data-pagebreak-tags="Rust, Web Dev">Item 1</p>
+        <p data-pagebreak-tags="Web Dev">Item 2</p>
+        <p>Item 3</p>
+      </section>
+      """
+    When I run Pagebreak
+    Then I should see "Item 3" in "output/page/3/index.html"
+    And I should see "Item 1" in "output/tag/rust/index.html"
+    And I should see "Posts tagged Rust" in "output/tag/rust/index.html"
+    And I should see "Item 1" in "output/tag/web-dev/index.html"
+    And I should see "Item 2" in "output/tag/web-dev/page/2/index.html"
+    But I should not see the file "output/tag/rust/page/2/index.html"
+    And I should not see the file "output/tag/web-dev/page/3/index.html"
+    And I should not see "data-pagebreak-tags" in "output/index.html"
+
+  Scenario: If I set a tag URL, tag listings should be written there
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="1" data-pagebreak-tag-url="./topics/:tag/">
+        <p data-pagebreak-tags="Rust">Item 1</p>
+        <p data-pagebreak-tags="Rust">Item 2</p>
+      </section>
+      """
+    When I run Pagebreak
+    Then I should see "Item 1" in "output/topics/rust/index.html"
+    And I should see "Item 2" in "output/topics/rust/page/2/index.html"
+    But I should not see "data-pagebreak-tag-url" in "output/index.html"
+
+  Scenario: If I paginate an archive, page numbers should count up from the oldest page
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="2" data-pagebreak-archive>
+        <p>Item 5</p>
+        <p>Item 4</p>
+        <p>Item 3</p>
+        <p>Item 2</p>
+        <p>Item 1</p>
+      </section>
+      <p>Page <span data-pagebreak-label="current"></span></p>
+      """
+    When I run Pagebreak
+    Then I should see "Item 5" in "output/index.html"
+    And I should see "Page <span>3</span>" in "output/index.html"
+    And I should see "Item 4" in "output/page/2/index.html"
+    And I should see "Item 3" in "output/page/2/index.html"
+    And I should see "Item 2" in "output/page/1/index.html"
+    And I should see "Item 1" in "output/page/1/index.html"
+    And I should see "Page <span>1</span>" in "output/page/1/index.html"
+    But I should not see "Item 4" in "output/index.html"
+    And I should not see the file "output/page/3/index.html"
+    And I should not see "data-pagebreak-archive" in "output/index.html"
+
+  Scenario: If two containers share a tag, the second container's listing should not overwrite the first
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="1">
+        <p data-pagebreak-tags="rust">Post 1</p>
+      </section>
+      <section data-pagebreak="1" data-pagebreak-url="./events/:num/">
+        <p data-pagebreak-tags="rust">Event 1</p>
+        <p data-pagebreak-tags="rust">Event 2</p>
+      </section>
+      """
+    When I run Pagebreak
+    Then I should see "Post 1" in "output/tag/rust/index.html"
+    But I should not see the file "output/tag/rust/events/2/index.html"
+
+  Scenario: If two containers write a view all page, the second should not overwrite the first
+    Given I have a "source/index.html" file with the body:
+      """
+      <section data-pagebreak="1" data-pagebreak-all>
+        <p>Post 1</p>
+        <p>Post 2</p>
+      </section>
+      <section data-pagebreak="1" data-pagebreak-all data-pagebreak-url="./events/:num/">
+        <p>Event 1</p>
+        <p>Event 2</p>
+      </section>
+      """
+    When I run Pagebreak
+    Then I should see "Post 2" in "output/all/index.html"
+    But I should not see "Event 2" in "output/all/index.html"
@@ -3,6 +3,7 @@ use std::fmt;
 #[derive(Debug, PartialEq)]
 pub enum PageErrorCode {
     ParentDir,
+    DuplicateUrl,
 }
 
 pub struct PageError {
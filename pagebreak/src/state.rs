@@ -1,20 +1,231 @@
 use crate::errors;
-use kuchiki::{ElementData, NodeDataRef, NodeRef};
+use kuchiki::{ElementData, NodeData, NodeDataRef, NodeRef, Selectors};
 use lexiclean::Lexiclean;
-use std::cell::RefCell;
-use std::path::Component;
-use std::rc::Rc;
+use std::cmp::Ordering;
+use std::ops::Range;
+use std::path::{Component, Path};
 use std::{fs, path::PathBuf};
 
 #[derive(Debug)]
 pub struct PagebreakNode {
-    element: Option<NodeRef>,
+    element: NodeRef,
+    /// Position of this item in the original markup
+    index: usize,
+    key: Option<String>,
+    tags: Vec<String>,
+    weight: Option<usize>,
+    pin: Option<PagebreakPin>,
+    is_static: bool,
+    insert_every: Option<usize>,
+    starts_item: bool,
+    /// The format used to repeat this heading on continuation pages, if this is a heading
+    heading: Option<String>,
+    is_break: bool,
+    is_placeholder: bool,
+    /// Whether a page break marker came before this item
+    breaks_before: bool,
+    /// Following siblings that belong to this item, such as the `dd` of a `dt`
+    continuation: Vec<NodeRef>,
 }
 
 impl PagebreakNode {
     pub fn new(element: NodeRef) -> Self {
+        let take_attribute = |attribute: &str| {
+            element.as_element().and_then(|element| {
+                let mut attributes = element.attributes.borrow_mut();
+                let value = attributes.get(attribute).map(String::from);
+                attributes.remove(attribute);
+                value
+            })
+        };
+        let key = take_attribute("data-pagebreak-key");
+        let tags = take_attribute("data-pagebreak-tags")
+            .map(|tags| {
+                tags.split(',')
+                    .map(|tag| tag.trim().to_string())
+                    .filter(|tag| !tag.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let weight = take_attribute("data-pagebreak-weight")
+            .and_then(|weight| parse_number("data-pagebreak-weight", &weight));
+        let pin = match take_attribute("data-pagebreak-pin").as_deref() {
+            None => None,
+            Some("bottom") => Some(PagebreakPin::Bottom),
+            Some(_) => Some(PagebreakPin::Top),
+        };
+        let is_static = take_attribute("data-pagebreak-static").is_some();
+        let insert_every = take_attribute("data-pagebreak-insert-every")
+            .and_then(|every| parse_number("data-pagebreak-insert-every", &every))
+            .filter(|every| *every > 0);
+        let starts_item = take_attribute("data-pagebreak-item-start").is_some();
+        let heading = take_attribute("data-pagebreak-heading");
+        let is_break = take_attribute("data-pagebreak-break").is_some();
+        let is_placeholder = take_attribute("data-pagebreak-empty").is_some();
         PagebreakNode {
-            element: Some(element),
+            element,
+            index: 0,
+            key,
+            tags,
+            weight,
+            pin,
+            is_static,
+            insert_every,
+            starts_item,
+            heading,
+            is_break,
+            is_placeholder,
+            breaks_before: false,
+            continuation: Vec::default(),
+        }
+    }
+
+    /// Fresh copies of the contents of this item's template, skipping whitespace
+    fn template_contents(&self) -> Vec<NodeRef> {
+        let contents = self
+            .element
+            .as_element()
+            .and_then(|element| element.template_contents.clone());
+        contents
+            .iter()
+            .flat_map(|contents| contents.children())
+            .filter(|child| {
+                !child
+                    .as_text()
+                    .is_some_and(|text| text.borrow().trim().is_empty())
+            })
+            .map(|child| deep_clone(&child))
+            .collect()
+    }
+
+    /// How much of a page this item takes up, unless given an explicit weight
+    fn measure(&self, measure: &PagebreakMeasure) -> usize {
+        match measure {
+            PagebreakMeasure::Count => 1,
+            PagebreakMeasure::Bytes => {
+                let mut bytes = vec![];
+                self.elements()
+                    .for_each(|element| element.serialize(&mut bytes).unwrap());
+                bytes.len()
+            }
+            PagebreakMeasure::Words => self
+                .elements()
+                .map(|element| element.text_contents().split_whitespace().count())
+                .sum(),
+        }
+    }
+
+    /// This heading as repeated at the top of a page, with its text replaced by the format
+    /// if one was given
+    fn continued_heading(&self) -> NodeRef {
+        let format = self.heading.as_deref().unwrap_or_default();
+        if format.is_empty() {
+            return self.element.clone();
+        }
+        let heading = deep_clone(&self.element);
+        let content = heading.text_contents();
+        heading.children().for_each(|child| child.detach());
+        heading.append(NodeRef::new_text(
+            format.replace(":content", content.trim()),
+        ));
+        heading
+    }
+
+    /// Every element that makes up this item
+    fn elements(&self) -> impl Iterator<Item = &NodeRef> {
+        std::iter::once(&self.element).chain(self.continuation.iter())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum PagebreakPin {
+    Top,
+    Bottom,
+}
+
+#[derive(Debug, PartialEq)]
+enum PagebreakMeasure {
+    Count,
+    Bytes,
+    Words,
+}
+
+/// An additional series of a group's items, written within its own directory
+#[derive(Clone, Debug, PartialEq)]
+struct PagebreakListing {
+    path: String,
+    sort: Option<PagebreakSort>,
+    tag: Option<String>,
+    /// Whether every item is shown on a single page
+    all: bool,
+}
+
+impl PagebreakListing {
+    /// The attribute that sets where this listing is written
+    fn url_attribute(&self) -> &'static str {
+        if self.all {
+            "data-pagebreak-all"
+        } else if self.tag.is_some() {
+            "data-pagebreak-tag-url"
+        } else {
+            "data-pagebreak-sorts"
+        }
+    }
+}
+
+/// An order for items, by the value of an attribute or by their text
+#[derive(Clone, Debug, PartialEq)]
+struct PagebreakSort {
+    attribute: Option<String>,
+    descending: bool,
+}
+
+impl PagebreakSort {
+    /// Parses a sort such as `data-date:desc`, `data-title` or `text:asc`
+    fn parse(sort: &str) -> Self {
+        let sort = sort.trim();
+        let (key, descending) = match sort.rsplit_once(':') {
+            Some((key, "desc")) => (key, true),
+            Some((key, "asc")) => (key, false),
+            _ => (sort, false),
+        };
+        PagebreakSort {
+            attribute: (key != "text").then(|| key.to_string()),
+            descending,
+        }
+    }
+
+    fn value(&self, item: &PagebreakNode) -> String {
+        match &self.attribute {
+            Some(attribute) => item
+                .element
+                .as_element()
+                .and_then(|element| {
+                    element
+                        .attributes
+                        .borrow()
+                        .get(&attribute[..])
+                        .map(String::from)
+                })
+                .unwrap_or_default(),
+            None => item.element.text_contents().trim().to_string(),
+        }
+    }
+
+    /// Compares values as numbers where both are numeric, otherwise as text,
+    /// with numeric values ordered before all others
+    fn compare(&self, a: &PagebreakNode, b: &PagebreakNode) -> Ordering {
+        let (a, b) = (self.value(a), self.value(b));
+        let ordering = match (a.parse::<f64>(), b.parse::<f64>()) {
+            (Ok(a), Ok(b)) => a.total_cmp(&b),
+            (Ok(_), Err(_)) => Ordering::Less,
+            (Err(_), Ok(_)) => Ordering::Greater,
+            (Err(_), Err(_)) => a.cmp(&b),
+        };
+        if self.descending {
+            ordering.reverse()
+        } else {
+            ordering
         }
     }
 }
@@ -33,14 +244,37 @@ enum PagebreakChange {
     },
 }
 
+impl PagebreakChange {
+    fn format(&self) -> &str {
+        match self {
+            PagebreakChange::Content { format, .. } | PagebreakChange::Attribute { format, .. } => {
+                format
+            }
+        }
+    }
+}
+
 #[derive(Debug, PartialEq)]
 enum PagebreakElementType {
+    Format(String),
     Next,
     Previous,
     NoNext,
     NoPrevious,
+    First,
+    Last,
+    NoFirst,
+    NoLast,
+    Pages,
+    Ellipsis,
+    Tags,
+    Overflow,
+    All,
     Current,
     Total,
+    FirstItem,
+    LastItem,
+    TotalItems,
     None,
 }
 struct PagebreakElement {
@@ -48,12 +282,14 @@ struct PagebreakElement {
     parent: Option<NodeRef>,
     previous_sibling: Option<NodeRef>,
     element_type: PagebreakElementType,
+    group: Option<usize>,
 }
 
 impl PagebreakElement {
     pub fn new(
         element: NodeRef,
         element_type: PagebreakElementType,
+        group: Option<usize>,
         parent: Option<NodeRef>,
         previous_sibling: Option<NodeRef>,
     ) -> Self {
@@ -62,294 +298,313 @@ impl PagebreakElement {
             parent,
             previous_sibling,
             element_type,
+            group,
         }
     }
 }
 
-pub struct PagebreakState {
-    pub document: NodeRef,
-    file_path: PathBuf,
-    output_path: PathBuf,
-    page_container: Option<NodeDataRef<ElementData>>,
-    page_items: Option<Rc<RefCell<Vec<PagebreakNode>>>>,
-    page_count: Option<usize>,
-    per_page: Option<usize>,
-    page_url_format: String,
-    page_meta_format: String,
+/// A single `[data-pagebreak]` element and the items within it
+pub struct PagebreakContainer {
+    id: Option<String>,
+    node: NodeDataRef<ElementData>,
+    items: Vec<PagebreakNode>,
+    /// Items left out of the listing currently being shown, or beyond its maximum pages
+    filtered_items: Vec<PagebreakNode>,
+    pinned_items: Vec<PagebreakNode>,
+    /// Children that stay in place, alongside the number of items that preceded them
+    static_items: Vec<(usize, PagebreakNode)>,
+    /// Templates cloned after every n items on a page
+    inserts: Vec<PagebreakNode>,
+    /// Headings for the items after them, alongside the index of the item that follows them
+    headings: Vec<(usize, PagebreakNode)>,
+    /// Children shown only when there are no items
+    placeholders: Vec<PagebreakNode>,
+    /// Where each placeholder sat in the original markup, when items are chosen by a selector
+    placeholder_slots: Vec<NodeRef>,
+    /// Selector for items nested anywhere within the container, instead of its children
+    item_selector: Option<String>,
+    /// Where each selected item sat in the original markup, filled in order on every page
+    slots: Vec<NodeRef>,
+    /// Selector for the children that begin an item, which the children after them join
+    item_start: Option<Selectors>,
+    /// Selector for the elements an article is split before, if this container is an article
+    article: Option<String>,
+    pages: Vec<Range<usize>>,
+    per_page: usize,
+    first_page: Option<usize>,
+    orphans: usize,
+    max_pages: Option<usize>,
+    /// Whether items were left out of the series to keep within `max_pages`
+    overflows: bool,
+    measure: PagebreakMeasure,
+    sort: Option<PagebreakSort>,
+    archive: bool,
     dom_indentation: String,
-    pagebreak_elements: Option<Vec<PagebreakElement>>,
-    changes: Vec<PagebreakChange>,
 }
 
-impl PagebreakState {
-    pub fn new(document: NodeRef, file_path: PathBuf, output_path: PathBuf) -> Self {
-        PagebreakState {
-            document,
-            file_path,
-            output_path,
-            page_container: None,
-            page_items: None,
-            page_count: None,
-            per_page: None,
-            page_url_format: "./page/:num/".to_string(),
-            page_meta_format: ":content | Page :num".to_string(),
+impl PagebreakContainer {
+    pub fn new(node: NodeDataRef<ElementData>) -> Self {
+        let id = node.attributes.borrow().get("id").map(String::from);
+        PagebreakContainer {
+            id,
+            node,
+            items: Vec::default(),
+            filtered_items: Vec::default(),
+            pinned_items: Vec::default(),
+            static_items: Vec::default(),
+            inserts: Vec::default(),
+            headings: Vec::default(),
+            placeholders: Vec::default(),
+            placeholder_slots: Vec::default(),
+            item_selector: None,
+            slots: Vec::default(),
+            item_start: None,
+            article: None,
+            pages: Vec::default(),
+            per_page: 2,
+            first_page: None,
+            orphans: 0,
+            max_pages: None,
+            overflows: false,
+            measure: PagebreakMeasure::Count,
+            sort: None,
+            archive: false,
             dom_indentation: "\n".to_string(),
-            pagebreak_elements: None,
-            changes: Vec::default(),
         }
     }
 
-    pub fn hydrate(&mut self) {
-        self.find_pagebreak_node();
-        if self.page_container.is_some() {
-            self.read_meta_format();
-            self.read_pagebreak_node();
-            self.find_pagination_children();
-            self.find_pagebreak_elements();
-            self.find_changes();
-            self.page_count = Some(
-                (self.page_items.as_ref().unwrap().borrow().len() + self.per_page.unwrap() - 1)
-                    / self.per_page.unwrap(),
-            );
-        }
+    fn hydrate(&mut self) {
+        self.read_pagebreak_node();
+        self.find_pagination_children();
+        self.arrange_items(None, None, false);
     }
 
-    fn find_changes(&mut self) {
-        if let Ok(select) = self.document.select("title") {
-            select.for_each(|element| {
-                self.changes.push(PagebreakChange::Content {
-                    node: element.as_node().clone(),
-                    original_content: element.text_contents(),
-                    format: self.page_meta_format.clone(),
-                })
-            });
-        }
-
-        if let Ok(select) = self.document.select("[property=\"og:title\"]") {
-            select.for_each(|element| {
-                self.changes.push(PagebreakChange::Attribute {
-                    node: element.as_node().clone(),
-                    original_content: String::from(
-                        element.attributes.borrow().get("content").unwrap(),
-                    ),
-                    attribute: String::from("content"),
-                    format: self.page_meta_format.clone(),
-                })
-            });
-        }
-
-        if let Ok(select) = self.document.select("[property=\"twitter:title\"]") {
-            select.for_each(|element| {
-                self.changes.push(PagebreakChange::Attribute {
-                    node: element.as_node().clone(),
-                    original_content: String::from(
-                        element.attributes.borrow().get("content").unwrap(),
-                    ),
-                    attribute: String::from("content"),
-                    format: self.page_meta_format.clone(),
-                })
-            });
-        }
-
-        if let Ok(select) = self.document.select("[href]") {
-            select
-                .filter(|element| {
-                    let attributes = element.as_node().as_element().unwrap().attributes.borrow();
-                    let url = attributes.get("href").unwrap();
-                    url.len() > 0
-                        && !url.starts_with("http://")
-                        && !url.starts_with("https://")
-                        && !url.starts_with('/')
-                        && !url.starts_with('#')
-                })
-                .for_each(|element| {
-                    self.changes.push(PagebreakChange::Attribute {
-                        node: element.as_node().clone(),
-                        original_content: String::from(
-                            element.attributes.borrow().get("href").unwrap(),
-                        ),
-                        attribute: String::from("href"),
-                        format: String::from(":rel-from:content"),
-                    })
-                });
+    /// Orders the items by the given sort, or by this container's own sort if none is given,
+    /// keeps only those with the given tag, and splits them into pages unless `all` are shown at once
+    fn arrange_items(&mut self, sort: Option<&PagebreakSort>, tag: Option<&str>, all: bool) {
+        let sort = sort.or(self.sort.as_ref()).cloned();
+        self.items.append(&mut self.filtered_items);
+        self.items.sort_by_key(|item| item.index);
+        if let Some(tag) = tag.map(slugify) {
+            let (items, filtered_items) = std::mem::take(&mut self.items)
+                .into_iter()
+                .partition(|item| item.tags.iter().any(|item_tag| slugify(item_tag) == tag));
+            self.items = items;
+            self.filtered_items = filtered_items;
         }
-
-        if let Ok(select) = self.document.select("[rel=\"canonical\"]") {
-            select.for_each(|element| {
-                self.changes.push(PagebreakChange::Attribute {
-                    node: element.as_node().clone(),
-                    original_content: String::from(
-                        element.attributes.borrow().get("href").unwrap(),
-                    ),
-                    attribute: String::from("href"),
-                    format: String::from(":content:rel-to"),
-                })
-            });
+        if let Some(sort) = sort {
+            self.items.sort_by(|a, b| sort.compare(a, b));
         }
-
-        if let Ok(select) = self.document.select("[property=\"og:url\"]") {
-            select.for_each(|element| {
-                self.changes.push(PagebreakChange::Attribute {
-                    node: element.as_node().clone(),
-                    original_content: String::from(
-                        element.attributes.borrow().get("content").unwrap(),
-                    ),
-                    attribute: String::from("content"),
-                    format: String::from(":content:rel-to"),
-                })
-            });
+        if all {
+            self.pages.clear();
+            if !self.items.is_empty() {
+                self.pages.push(0..self.items.len());
+            }
+            self.overflows = false;
+            return;
         }
+        self.split_pages();
+        self.fold_orphans();
+        self.limit_pages();
     }
 
-    pub fn paginate(&mut self) {
-        if self.page_container.is_none() {
-            return;
-        };
-        for page_number in 0..self.page_count.unwrap() {
-            self.detach_children();
-            let remaining_items = self.page_items.as_ref().unwrap().clone();
-            let mut remaining_items = remaining_items.borrow_mut();
-            let max_count = self.per_page.unwrap().min(remaining_items.len());
-
-            remaining_items.drain(0..max_count).for_each(|element| {
-                self.indent_for_next_element();
-                self.reattach_child(element);
-            });
+    fn page_count(&self) -> usize {
+        self.pages.len()
+    }
 
-            self.indent_for_next_element();
-            self.update_elements_for_page(page_number, self.page_count.unwrap());
-            self.apply_changes(page_number);
+    /// The indexes of the items shown on the given page
+    fn page_range(&self, page_index: usize) -> Range<usize> {
+        let end = self.items.len();
+        self.pages.get(page_index).cloned().unwrap_or(end..end)
+    }
 
-            let cleaned_file_url = self.get_file_url(page_number);
-            let file_url = match cleaned_file_url {
-                Ok(url) => url,
-                Err(err) => {
-                    eprintln!("{:?}\nPagebreak: Skipping errored page", err);
-                    return;
-                }
+    /// Splits the items into pages holding up to `per_page` worth of item weight,
+    /// after a first page of `first_page` if one was given.
+    /// A new page is also started wherever the key of the items changes,
+    /// and wherever a page break marker was placed.
+    fn split_pages(&mut self) {
+        // Archives are filled from the oldest item at the end, so that older pages never change
+        if self.archive {
+            self.items.reverse();
+        }
+        let weights: Vec<usize> = self
+            .items
+            .iter()
+            .map(|item| item.weight.unwrap_or_else(|| item.measure(&self.measure)))
+            .collect();
+        let mut pages = vec![];
+        let mut start = 0;
+        while start < self.items.len() {
+            let page_size = if pages.is_empty() {
+                self.first_page.unwrap_or(self.per_page)
+            } else {
+                self.per_page
             };
-
-            let output_file_path = self.output_path.join(file_url);
-            fs::create_dir_all(&output_file_path.parent().unwrap()).unwrap();
-            self.write_current_document_to_disk(output_file_path);
-
-            self.reattach_elements();
+            let mut end = start;
+            let mut page_weight: usize = 0;
+            while end < self.items.len() {
+                // Every page holds at least one item, even if it is too heavy to fit
+                let fits = end == start || page_weight.saturating_add(weights[end]) <= page_size;
+                // Markers break before an item, which comes after it when filling an archive
+                let marked = end > start
+                    && if self.archive {
+                        self.items[end - 1].breaks_before
+                    } else {
+                        self.items[end].breaks_before
+                    };
+                if !fits || marked || self.items[end].key != self.items[start].key {
+                    break;
+                }
+                page_weight = page_weight.saturating_add(weights[end]);
+                end += 1;
+            }
+            pages.push(start..end);
+            start = end;
+        }
+        if self.archive {
+            self.items.reverse();
+            let len = self.items.len();
+            pages = pages
+                .into_iter()
+                .rev()
+                .map(|page| len - page.end..len - page.start)
+                .collect();
         }
+        self.pages = pages;
     }
 
-    pub fn apply_changes(&mut self, page_index: usize) {
-        if page_index == 0 {
+    /// Merges a final page holding fewer than `orphans` items into the page before it,
+    /// as long as both pages share the same key.
+    /// In an archive the partial page is the first page, which is merged into the page after it
+    fn fold_orphans(&mut self) {
+        if self.pages.len() < 2 {
             return;
         }
-
-        for change in &self.changes {
-            match change {
-                PagebreakChange::Content {
-                    node,
-                    original_content,
-                    format,
-                } => {
-                    let resolved_content =
-                        self.resolve_format(format, page_index, original_content);
-
-                    node.children().for_each(|child| child.detach());
-                    node.append(NodeRef::new_text(&resolved_content))
-                }
-                PagebreakChange::Attribute {
-                    node,
-                    attribute,
-                    original_content,
-                    format,
-                } => {
-                    let mut attributes = node.as_element().unwrap().attributes.borrow_mut();
-                    let attribute = &attribute[..];
-
-                    if attributes.get(attribute).is_some() {
-                        let resolved_content =
-                            self.resolve_format(format, page_index, original_content);
-                        attributes.remove(attribute);
-                        attributes.insert(attribute, resolved_content);
-                    }
-                }
+        let (orphan, neighbour) = if self.archive {
+            (0, 1)
+        } else {
+            (self.pages.len() - 1, self.pages.len() - 2)
+        };
+        let page = self.pages[orphan].clone();
+        let neighbour = &self.pages[neighbour];
+        if page.len() < self.orphans
+            && self.items[page.start].key == self.items[neighbour.start].key
+        {
+            self.pages.remove(orphan);
+            if self.archive {
+                self.pages[0].start = page.start;
+            } else {
+                self.pages.last_mut().unwrap().end = page.end;
             }
         }
     }
 
-    pub fn detach_children(&mut self) {
-        self.page_container
-            .as_ref()
-            .unwrap()
-            .as_node()
-            .children()
-            .for_each(|child| {
-                child.detach();
-            });
-    }
-
-    pub fn reattach_child(&mut self, mut child: PagebreakNode) {
-        self.page_container
-            .as_ref()
-            .unwrap()
-            .as_node()
-            .append(child.element.take().unwrap());
+    /// Drops the pages after `max_pages`, leaving the items on them out of the series
+    fn limit_pages(&mut self) {
+        self.overflows = self
+            .max_pages
+            .is_some_and(|max_pages| self.pages.len() > max_pages);
+        if !self.overflows {
+            return;
+        }
+        self.pages.truncate(self.max_pages.unwrap());
+        let end = self.pages.last().unwrap().end;
+        self.filtered_items.extend(self.items.drain(end..));
     }
 
-    fn read_meta_format(&mut self) {
-        let mut attributes = self
-            .page_container
-            .as_ref()
-            .unwrap()
-            .as_node()
-            .as_element()
-            .unwrap()
-            .attributes
-            .borrow_mut();
-
-        if let Some(format) = attributes.get("data-pagebreak-meta") {
-            self.page_meta_format = format.to_string();
-            attributes.remove("data-pagebreak-meta");
-        }
+    /// The key of the first item on the given page
+    fn page_key(&self, page_index: usize) -> Option<&str> {
+        self.items
+            .get(self.page_range(page_index).start)
+            .and_then(|item| item.key.as_deref())
     }
 
-    fn find_pagebreak_node(&mut self) {
-        self.page_container = self.document.select("[data-pagebreak]").unwrap().next();
+    /// Reads an attribute from the container, removing it from the output
+    fn take_attribute(&self, attribute: &str) -> Option<String> {
+        let mut attributes = self.node.attributes.borrow_mut();
+        let value = attributes.get(attribute).map(String::from);
+        attributes.remove(attribute);
+        value
     }
 
     fn read_pagebreak_node(&mut self) {
-        let mut pagination_attributes = self
-            .page_container
-            .as_ref()
-            .unwrap()
-            .as_node()
-            .as_element()
-            .unwrap()
-            .attributes
-            .borrow_mut();
-        self.page_url_format = pagination_attributes
-            .get("data-pagebreak-url")
-            .unwrap_or("./page/:num/")
-            .to_string();
-        pagination_attributes.remove("data-pagebreak-url");
-        self.per_page = Some(
-            pagination_attributes
-                .get("data-pagebreak")
-                .unwrap_or("2")
-                .parse::<usize>()
-                .unwrap(),
-        );
-        pagination_attributes.remove("data-pagebreak");
+        self.article = self.take_attribute("data-pagebreak-article");
+        // Articles are split into a section per page unless told otherwise
+        let default_per_page = if self.article.is_some() { 1 } else { 2 };
+        self.per_page = match self.take_attribute("data-pagebreak").as_deref() {
+            None => default_per_page,
+            // Without a page size, pages are only split at break markers
+            Some("") => usize::MAX,
+            Some(per_page) => parse_number("data-pagebreak", per_page).unwrap_or(default_per_page),
+        };
+        self.first_page = self
+            .take_attribute("data-pagebreak-first")
+            .and_then(|first_page| parse_number("data-pagebreak-first", &first_page));
+        self.orphans = self
+            .take_attribute("data-pagebreak-orphans")
+            .and_then(|orphans| parse_number("data-pagebreak-orphans", &orphans))
+            .unwrap_or(0);
+        self.max_pages = self
+            .take_attribute("data-pagebreak-max-pages")
+            .and_then(|max_pages| parse_number("data-pagebreak-max-pages", &max_pages))
+            .filter(|max_pages| *max_pages > 0);
+        self.measure = match self.take_attribute("data-pagebreak-measure").as_deref() {
+            None | Some("count") => PagebreakMeasure::Count,
+            Some("bytes") => PagebreakMeasure::Bytes,
+            Some("words") => PagebreakMeasure::Words,
+            Some(measure) => {
+                eprintln!(
+                    "Pagebreak: Unknown data-pagebreak-measure {:?}, counting items instead",
+                    measure
+                );
+                PagebreakMeasure::Count
+            }
+        };
+        self.sort = self
+            .take_attribute("data-pagebreak-sort")
+            .map(|sort| PagebreakSort::parse(&sort));
+        self.archive = self.take_attribute("data-pagebreak-archive").is_some();
+        // Capping an archive would drop its oldest pages and renumber the rest
+        if self.archive && self.max_pages.take().is_some() {
+            eprintln!(
+                "Pagebreak: data-pagebreak-max-pages can't be used with data-pagebreak-archive, writing every page instead"
+            );
+        }
+        self.item_selector = self.take_attribute("data-pagebreak-items");
+        self.item_start = self
+            .take_attribute("data-pagebreak-item-start")
+            .and_then(|selector| match Selectors::compile(&selector) {
+                Ok(selectors) => Some(selectors),
+                Err(_) => {
+                    eprintln!(
+                        "Pagebreak: Invalid data-pagebreak-item-start selector {:?}",
+                        selector
+                    );
+                    None
+                }
+            });
     }
 
     fn find_pagination_children(&mut self) {
-        let mut nodes = self.page_container.as_ref().unwrap().as_node().children();
+        if let Some(selector) = self.article.clone() {
+            self.find_article_sections(&selector);
+            return;
+        }
+        if let Some(selector) = self.item_selector.clone() {
+            self.find_selected_items(&selector);
+            return;
+        }
+
+        let mut nodes = self.node.as_node().children();
         let mut children = vec![];
 
-        let first_child = nodes.next().unwrap();
-        if first_child.as_text().is_some() {
-            let val = first_child.as_text().unwrap().borrow();
-            self.dom_indentation = val.to_string();
-        } else if first_child.as_element().is_some() {
-            children.push(PagebreakNode::new(first_child));
+        if let Some(first_child) = nodes.next() {
+            if first_child.as_text().is_some() {
+                let val = first_child.as_text().unwrap().borrow();
+                self.dom_indentation = val.to_string();
+            } else if first_child.as_element().is_some() {
+                children.push(PagebreakNode::new(first_child));
+            }
         }
 
         for element in nodes {
@@ -359,24 +614,1343 @@ impl PagebreakState {
             }
         }
 
-        self.page_items = Some(Rc::new(RefCell::new(children)));
+        let joins_items =
+            self.item_start.is_some() || children.iter().any(|child| child.starts_item);
+        let starts_item = |child: &PagebreakNode| {
+            child.starts_item
+                || self.item_start.as_ref().is_some_and(|selectors| {
+                    child
+                        .element
+                        .clone()
+                        .into_element_ref()
+                        .is_some_and(|element| selectors.matches(&element))
+                })
+        };
+
+        let mut items: Vec<PagebreakNode> = vec![];
+        let mut pinned_items = vec![];
+        let mut static_items = vec![];
+        let mut inserts = vec![];
+        let mut headings = vec![];
+        let mut placeholders = vec![];
+        let mut breaks_before = false;
+        for mut child in children {
+            let is_item = child.insert_every.is_none()
+                && !child.is_static
+                && child.pin.is_none()
+                && child.heading.is_none()
+                && !child.is_break
+                && !child.is_placeholder;
+            if is_item && joins_items && !items.is_empty() && !starts_item(&child) {
+                items.last_mut().unwrap().continuation.push(child.element);
+            } else if child.is_break {
+                // Markers are left out of the output
+                breaks_before = true;
+            } else if child.is_placeholder {
+                placeholders.push(child);
+            } else if child.heading.is_some() {
+                headings.push((items.len(), child));
+            } else if child.insert_every.is_some() {
+                inserts.push(child);
+            } else if child.is_static {
+                static_items.push((items.len(), child));
+            } else if child.pin.is_some() {
+                pinned_items.push(child);
+            } else {
+                child.index = items.len();
+                child.breaks_before = breaks_before;
+                breaks_before = false;
+                items.push(child);
+            }
+        }
+        self.items = items;
+        self.pinned_items = pinned_items;
+        self.static_items = static_items;
+        self.inserts = inserts;
+        self.headings = headings;
+        self.placeholders = placeholders;
     }
 
-    fn find_pagebreak_elements(&mut self) {
-        let mut elements = vec![];
-        self.document
+    /// Splits the content of an article into sections before each element matching the selector,
+    /// and at each `<!-- more -->` comment. Each section becomes an item, holding copies of
+    /// the wrappers around its content.
+    fn find_article_sections(&mut self, selector: &str) {
+        let selectors = match selector {
+            "" => None,
+            selector => Selectors::compile(selector)
+                .map_err(|_| {
+                    eprintln!(
+                        "Pagebreak: Invalid data-pagebreak-article selector {:?}",
+                        selector
+                    )
+                })
+                .ok(),
+        };
+
+        // Controls would be copied into whichever section they fall in, so they are moved after the article
+        let container = self.node.as_node();
+        let controls: Vec<NodeRef> = container
             .select("[data-pagebreak-control], [data-pagebreak-label]")
             .unwrap()
-            .for_each(|element| {
-                let element_node = element.as_node();
-                let mut element_attributes =
-                    element_node.as_element().unwrap().attributes.borrow_mut();
-                let (attribute, value) =
-                    if let Some(control) = element_attributes.get("data-pagebreak-control") {
-                        ("data-pagebreak-control", control)
-                    } else if let Some(label) = element_attributes.get("data-pagebreak-label") {
-                        ("data-pagebreak-label", label)
-                    } else {
+            .map(|control| control.as_node().clone())
+            .collect();
+        if !controls.is_empty() {
+            eprintln!(
+                "Pagebreak: Controls and labels can't sit within a data-pagebreak-article container, moving them after it"
+            );
+        }
+        // Labels within a control move along with it
+        let outer_controls: Vec<&NodeRef> = controls
+            .iter()
+            .filter(|control| {
+                !control
+                    .ancestors()
+                    .any(|ancestor| controls.contains(&ancestor))
+            })
+            .collect();
+        let mut previous = container.clone();
+        for control in outer_controls {
+            previous.insert_after(control.clone());
+            previous = control.clone();
+        }
+
+        // Placeholders are shown in place of the sections, rather than within them
+        let placeholders: Vec<NodeRef> = container
+            .select("[data-pagebreak-empty]")
+            .unwrap()
+            .map(|placeholder| placeholder.as_node().clone())
+            .collect();
+        for placeholder in placeholders {
+            placeholder.detach();
+            self.placeholders.push(PagebreakNode::new(placeholder));
+        }
+
+        let article = ArticleNodes::new(container);
+
+        let mut starts = vec![1];
+        starts.extend((1..article.nodes.len()).filter(|index| {
+            let node = &article.nodes[*index];
+            article.is_marker(*index)
+                || selectors.as_ref().is_some_and(|selectors| {
+                    node.clone()
+                        .into_element_ref()
+                        .is_some_and(|element| selectors.matches(&element))
+                })
+        }));
+        starts.dedup();
+
+        let mut ends: Vec<usize> = starts[1..].to_vec();
+        ends.push(article.nodes.len());
+
+        self.dom_indentation = String::new();
+        self.items = vec![];
+        for (start, end) in starts.into_iter().zip(ends) {
+            if !article.has_content(start..end) {
+                continue;
+            }
+            let mut section = article.clone_children(0, &(start..end)).into_iter();
+            let mut item = PagebreakNode::new(section.next().unwrap());
+            item.continuation = section.collect();
+            item.index = self.items.len();
+            self.items.push(item);
+        }
+    }
+
+    /// Items chosen by a selector stay wherever they sit in the container,
+    /// leaving a slot at each of their positions to be filled on every page
+    fn find_selected_items(&mut self, selector: &str) {
+        let container = self.node.as_node();
+        let elements: Vec<NodeRef> = match container.select(selector) {
+            Ok(select) => select
+                .map(|element| element.as_node().clone())
+                .filter(|element| element != container)
+                .collect(),
+            Err(_) => {
+                eprintln!(
+                    "Pagebreak: Invalid data-pagebreak-items selector {:?}",
+                    selector
+                );
+                vec![]
+            }
+        };
+
+        // Items nested within other items move along with them
+        let outer_elements: Vec<NodeRef> = elements
+            .iter()
+            .filter(|element| {
+                !element
+                    .ancestors()
+                    .any(|ancestor| elements.contains(&ancestor))
+            })
+            .cloned()
+            .collect();
+
+        let placeholders: Vec<NodeRef> = container
+            .select("[data-pagebreak-empty]")
+            .unwrap()
+            .map(|placeholder| placeholder.as_node().clone())
+            .filter(|placeholder| {
+                !placeholder
+                    .inclusive_ancestors()
+                    .any(|ancestor| outer_elements.contains(&ancestor))
+            })
+            .collect();
+        for placeholder in placeholders {
+            let slot = NodeRef::new_text("");
+            placeholder.insert_before(slot.clone());
+            self.placeholder_slots.push(slot);
+            self.placeholders.push(PagebreakNode::new(placeholder));
+        }
+
+        for element in outer_elements {
+            let slot = NodeRef::new_text("");
+            element.insert_before(slot.clone());
+            self.slots.push(slot);
+
+            let mut item = PagebreakNode::new(element);
+            item.index = self.items.len();
+            self.items.push(item);
+        }
+    }
+
+    fn pinned_items(&self, pin: PagebreakPin) -> impl Iterator<Item = &PagebreakNode> {
+        self.pinned_items
+            .iter()
+            .filter(move |item| item.pin.as_ref() == Some(&pin))
+    }
+
+    /// The index of the last heading before the given item in the original markup
+    fn heading_of(&self, item: &PagebreakNode) -> Option<usize> {
+        self.headings
+            .iter()
+            .rposition(|(position, _)| *position <= item.index)
+    }
+
+    /// Swaps the items in this container for those on the given page
+    pub fn show_page(&self, page_index: usize) {
+        if self.item_selector.is_some() {
+            self.items
+                .iter()
+                .chain(&self.filtered_items)
+                .for_each(|item| item.element.detach());
+            self.slots
+                .iter()
+                .zip(&self.items[self.page_range(page_index)])
+                .for_each(|(slot, item)| slot.insert_after(item.element.clone()));
+            for (slot, placeholder) in self.placeholder_slots.iter().zip(&self.placeholders) {
+                if self.items.is_empty() {
+                    slot.insert_after(placeholder.element.clone());
+                } else {
+                    placeholder.element.detach();
+                }
+            }
+            return;
+        }
+
+        let container = self.node.as_node();
+        container.children().for_each(|child| {
+            child.detach();
+        });
+
+        let mut page_nodes: Vec<NodeRef> = self
+            .pinned_items(PagebreakPin::Top)
+            .map(|item| item.element.clone())
+            .collect();
+
+        if self.items.is_empty() {
+            page_nodes.extend(
+                self.placeholders
+                    .iter()
+                    .map(|placeholder| placeholder.element.clone()),
+            );
+        }
+
+        // Static items keep their position relative to the item slots around them
+        let mut static_items = self.static_items.iter().peekable();
+        let page_range = self.page_range(page_index);
+        let mut previous_heading = page_range
+            .start
+            .checked_sub(1)
+            .and_then(|previous| self.heading_of(&self.items[previous]));
+        let mut shown_headings = vec![];
+        for (slot, item) in self.items[page_range].iter().enumerate() {
+            while let Some((_, static_item)) =
+                static_items.next_if(|(position, _)| *position <= slot)
+            {
+                page_nodes.push(static_item.element.clone());
+            }
+
+            let heading = self.heading_of(item);
+            if let Some(heading) = heading {
+                if previous_heading != Some(heading) {
+                    // Every heading directly before this item in the markup is shown,
+                    // copied if sorting has already placed it on this page
+                    let position = self.headings[heading].0;
+                    let run = self.headings.iter().enumerate();
+                    for (index, (_, heading)) in run.filter(|(_, (at, _))| *at == position) {
+                        if shown_headings.contains(&index) {
+                            page_nodes.push(deep_clone(&heading.element));
+                        } else {
+                            shown_headings.push(index);
+                            page_nodes.push(heading.element.clone());
+                        }
+                    }
+                } else if slot == 0 {
+                    // The heading of the previous page continues onto this one
+                    shown_headings.push(heading);
+                    page_nodes.push(self.headings[heading].1.continued_heading());
+                }
+            }
+            page_nodes.extend(item.elements().cloned());
+            previous_heading = heading;
+
+            for insert in &self.inserts {
+                if (slot + 1) % insert.insert_every.unwrap() == 0 {
+                    page_nodes.extend(insert.template_contents());
+                }
+            }
+        }
+        page_nodes.extend(static_items.map(|(_, static_item)| static_item.element.clone()));
+        page_nodes.extend(
+            self.pinned_items(PagebreakPin::Bottom)
+                .map(|item| item.element.clone()),
+        );
+
+        for node in page_nodes {
+            container.append(NodeRef::new_text(&self.dom_indentation));
+            container.append(node);
+        }
+
+        container.append(NodeRef::new_text(&self.dom_indentation));
+    }
+}
+
+/// The nodes of an article in document order, used to copy the parts of it between split points
+struct ArticleNodes {
+    nodes: Vec<NodeRef>,
+    /// The index of the last descendant of each node
+    ends: Vec<usize>,
+}
+
+impl ArticleNodes {
+    fn new(article: &NodeRef) -> Self {
+        let nodes: Vec<NodeRef> = article.inclusive_descendants().collect();
+        let ends = nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| index + node.descendants().count())
+            .collect();
+        ArticleNodes { nodes, ends }
+    }
+
+    fn is_marker(&self, index: usize) -> bool {
+        self.nodes[index]
+            .as_comment()
+            .is_some_and(|comment| comment.borrow().trim() == "more")
+    }
+
+    /// Whether anything other than whitespace and the wrappers of later content is in the range
+    fn has_content(&self, range: Range<usize>) -> bool {
+        range.clone().any(|index| {
+            let node = &self.nodes[index];
+            self.ends[index] < range.end
+                && !self.is_marker(index)
+                && (node.as_element().is_some()
+                    || node
+                        .as_text()
+                        .is_some_and(|text| !text.borrow().trim().is_empty()))
+        })
+    }
+
+    /// Copies of the children of the given node that fall within the range
+    fn clone_children(&self, index: usize, range: &Range<usize>) -> Vec<NodeRef> {
+        let mut children = vec![];
+        let mut child = index + 1;
+        while child <= self.ends[index] {
+            children.extend(self.clone_range(child, range));
+            child = self.ends[child] + 1;
+        }
+        children
+    }
+
+    /// Copies the given node if any of its content falls within the range.
+    /// Nodes that only partly fall within the range are copied without their other children
+    fn clone_range(&self, index: usize, range: &Range<usize>) -> Option<NodeRef> {
+        if self.ends[index] < range.start || index >= range.end || self.is_marker(index) {
+            return None;
+        }
+        if range.start <= index && self.ends[index] < range.end {
+            return Some(deep_clone(&self.nodes[index]));
+        }
+        let overlap = index.max(range.start)..(self.ends[index] + 1).min(range.end);
+        if !self.has_content(overlap) {
+            return None;
+        }
+        let clone = shallow_clone(&self.nodes[index]);
+        self.clone_children(index, range)
+            .into_iter()
+            .for_each(|child| clone.append(child));
+        Some(clone)
+    }
+}
+
+/// One or more containers that share a page sequence, paginated independently
+/// of any other groups in the document
+pub struct PagebreakGroup {
+    name: Option<String>,
+    /// The file holding the first page, which for nested containers is a page of their parent
+    file_path: PathBuf,
+    /// The file that was paginated, which links in the document are relative to
+    source_path: PathBuf,
+    containers: Vec<PagebreakContainer>,
+    page_count: usize,
+    page_url_format: String,
+    page_meta_format: String,
+    tag_url_format: String,
+    /// Additional series of the same items, such as alternate sorts or tags
+    listings: Vec<PagebreakListing>,
+    /// The listing currently being shown, if not the original series
+    listing: Option<usize>,
+    /// Whether canonical links point to the listing of every item, instead of each page
+    canonical_all: bool,
+    changes: Vec<PagebreakChange>,
+}
+
+impl PagebreakGroup {
+    pub fn new(name: Option<String>, file_path: PathBuf) -> Self {
+        PagebreakGroup {
+            name,
+            source_path: file_path.clone(),
+            file_path,
+            containers: Vec::default(),
+            page_count: 0,
+            page_url_format: "./page/:num/".to_string(),
+            page_meta_format: ":content | Page :num".to_string(),
+            tag_url_format: "./tag/:tag/".to_string(),
+            listings: Vec::default(),
+            listing: None,
+            canonical_all: false,
+            changes: Vec::default(),
+        }
+    }
+
+    fn hydrate(&mut self) {
+        self.read_meta_format();
+        self.read_url_format();
+        self.read_alternate_sorts();
+        self.read_tag_url_format();
+        self.containers
+            .iter_mut()
+            .for_each(|container| container.hydrate());
+        self.find_tags();
+        self.read_all_url();
+        self.count_pages();
+    }
+
+    /// Adds a listing for each tag found on the items, in the order they first appear.
+    /// Tags are matched by their slug, taking the name of their first appearance
+    fn find_tags(&mut self) {
+        let mut tags: Vec<&String> = vec![];
+        self.containers
+            .iter()
+            .flat_map(|container| container.items.iter())
+            .flat_map(|item| item.tags.iter())
+            .for_each(|tag| {
+                if !tags
+                    .iter()
+                    .any(|existing| slugify(existing) == slugify(tag))
+                {
+                    tags.push(tag);
+                }
+            });
+        let listings: Vec<PagebreakListing> = tags
+            .into_iter()
+            .map(|tag| PagebreakListing {
+                path: self.tag_url_format.replace(":tag", &slugify(tag)),
+                sort: None,
+                tag: Some(tag.clone()),
+                all: false,
+            })
+            .collect();
+        self.listings.extend(listings);
+    }
+
+    /// Adds a listing of every item on a single page, if one was asked for
+    /// with `data-pagebreak-all` or `data-pagebreak-canonical="all"`
+    fn read_all_url(&mut self) {
+        let urls: Vec<String> = self
+            .containers
+            .iter()
+            .filter_map(|container| container.take_attribute("data-pagebreak-all"))
+            .collect();
+        let canonicals: Vec<String> = self
+            .containers
+            .iter()
+            .filter_map(|container| container.take_attribute("data-pagebreak-canonical"))
+            .collect();
+
+        self.canonical_all = match canonicals.first().map(String::as_str) {
+            None => false,
+            Some("all") => true,
+            Some(canonical) => {
+                eprintln!(
+                    "Pagebreak: Unknown data-pagebreak-canonical {:?}, linking to each page instead",
+                    canonical
+                );
+                false
+            }
+        };
+
+        let path = match urls.into_iter().next() {
+            Some(url) if !url.is_empty() => url,
+            Some(_) => "./all/".to_string(),
+            None if self.canonical_all => "./all/".to_string(),
+            None => return,
+        };
+        self.listings.push(PagebreakListing {
+            path,
+            sort: None,
+            tag: None,
+            all: true,
+        });
+    }
+
+    /// The listing of every item on a single page, if there is one
+    fn all_listing(&self) -> Option<usize> {
+        self.listings.iter().position(|listing| listing.all)
+    }
+
+    /// Whether the listing of every item is the one currently being shown
+    fn shows_all(&self) -> bool {
+        self.listing.is_some() && self.listing == self.all_listing()
+    }
+
+    fn tags(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.listings
+            .iter()
+            .enumerate()
+            .filter_map(|(index, listing)| listing.tag.as_deref().map(|tag| (index, tag)))
+    }
+
+    /// The number shown for the given page, which in an archive counts up from the last page
+    fn page_number(&self, page_index: usize) -> usize {
+        let archive = self.containers.iter().any(|container| container.archive);
+        if archive {
+            self.page_count - page_index
+        } else {
+            page_index + 1
+        }
+    }
+
+    fn count_pages(&mut self) {
+        self.page_count = self
+            .containers
+            .iter()
+            .map(|container| container.page_count())
+            .max()
+            .unwrap_or(0)
+            // Containers without any items still show their first page
+            .max(1);
+    }
+
+    /// Switches to the alternate listing with the given index,
+    /// or back to the original listing if `None`
+    fn show_listing(&mut self, listing: Option<usize>) {
+        self.listing = listing;
+        let listing = listing.map(|listing| self.listings[listing].clone());
+        self.containers.iter_mut().for_each(|container| {
+            container.arrange_items(
+                listing.as_ref().and_then(|listing| listing.sort.as_ref()),
+                listing.as_ref().and_then(|listing| listing.tag.as_deref()),
+                listing.as_ref().is_some_and(|listing| listing.all),
+            )
+        });
+        self.count_pages();
+    }
+
+    /// The tag of the listing currently being shown
+    fn current_tag(&self) -> &str {
+        self.listing
+            .and_then(|listing| self.listings[listing].tag.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// The key of the first item on the given page, across every container in the group
+    fn page_key(&self, page_index: usize) -> String {
+        self.containers
+            .iter()
+            .find_map(|container| container.page_key(page_index))
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    /// Whether any container left items out of the series to keep within its maximum pages
+    fn overflows(&self) -> bool {
+        self.containers.iter().any(|container| container.overflows)
+    }
+
+    fn item_count(&self) -> usize {
+        self.containers
+            .iter()
+            .map(|container| container.items.len())
+            .sum()
+    }
+
+    /// The 1-based positions of the first and last items shown on the given page,
+    /// counting across every container in the group
+    fn item_range(&self, page_index: usize) -> (usize, usize) {
+        let (first, last) = self
+            .containers
+            .iter()
+            .map(|container| container.page_range(page_index))
+            .fold((1, 0), |(first, last), range| {
+                (first + range.start, last + range.end)
+            });
+        if last < first {
+            // A page without any items
+            (0, 0)
+        } else {
+            (first, last)
+        }
+    }
+
+    /// Whether controls bound to the given id belong to this group
+    fn matches(&self, id: &str) -> bool {
+        self.name.as_deref() == Some(id)
+            || self
+                .containers
+                .iter()
+                .any(|container| container.id.as_deref() == Some(id))
+    }
+
+    fn read_meta_format(&mut self) {
+        let formats: Vec<String> = self
+            .containers
+            .iter()
+            .filter_map(|container| container.take_attribute("data-pagebreak-meta"))
+            .collect();
+
+        if let Some(format) = formats.into_iter().next() {
+            self.page_meta_format = format;
+        }
+    }
+
+    fn read_url_format(&mut self) {
+        let formats: Vec<String> = self
+            .containers
+            .iter()
+            .filter_map(|container| container.take_attribute("data-pagebreak-url"))
+            .collect();
+
+        if let Some(format) = formats.into_iter().next() {
+            self.page_url_format = format;
+        }
+    }
+
+    /// Reads alternate sorts such as `by-title=data-title, by-price=data-price:desc`
+    fn read_alternate_sorts(&mut self) {
+        let sorts: Vec<String> = self
+            .containers
+            .iter()
+            .filter_map(|container| container.take_attribute("data-pagebreak-sorts"))
+            .collect();
+
+        if let Some(sorts) = sorts.into_iter().next() {
+            self.listings = sorts
+                .split(',')
+                .filter_map(|sort| sort.split_once('='))
+                .filter(|(name, _)| !name.trim().is_empty())
+                .map(|(name, sort)| PagebreakListing {
+                    path: name.trim().to_string(),
+                    sort: Some(PagebreakSort::parse(sort)),
+                    tag: None,
+                    all: false,
+                })
+                .collect();
+        }
+    }
+
+    fn read_tag_url_format(&mut self) {
+        let formats: Vec<String> = self
+            .containers
+            .iter()
+            .filter_map(|container| container.take_attribute("data-pagebreak-tag-url"))
+            .collect();
+
+        if let Some(format) = formats.into_iter().next() {
+            self.tag_url_format = format;
+        }
+    }
+
+    fn find_changes(&mut self, document: &NodeRef) {
+        if let Ok(select) = document.select("title") {
+            select.for_each(|element| {
+                self.changes.push(PagebreakChange::Content {
+                    node: element.as_node().clone(),
+                    original_content: element.text_contents(),
+                    format: self.page_meta_format.clone(),
+                })
+            });
+        }
+
+        if let Ok(select) = document.select("[property=\"og:title\"]") {
+            select.for_each(|element| {
+                self.changes.push(PagebreakChange::Attribute {
+                    node: element.as_node().clone(),
+                    original_content: String::from(
+                        element.attributes.borrow().get("content").unwrap(),
+                    ),
+                    attribute: String::from("content"),
+                    format: self.page_meta_format.clone(),
+                })
+            });
+        }
+
+        if let Ok(select) = document.select("[property=\"twitter:title\"]") {
+            select.for_each(|element| {
+                self.changes.push(PagebreakChange::Attribute {
+                    node: element.as_node().clone(),
+                    original_content: String::from(
+                        element.attributes.borrow().get("content").unwrap(),
+                    ),
+                    attribute: String::from("content"),
+                    format: self.page_meta_format.clone(),
+                })
+            });
+        }
+
+        if let Ok(select) = document.select("[href]") {
+            select
+                .filter(|element| {
+                    let attributes = element.as_node().as_element().unwrap().attributes.borrow();
+                    let url = attributes.get("href").unwrap();
+                    url.len() > 0
+                        && !url.starts_with("http://")
+                        && !url.starts_with("https://")
+                        && !url.starts_with('/')
+                        && !url.starts_with('#')
+                })
+                .for_each(|element| {
+                    self.changes.push(PagebreakChange::Attribute {
+                        node: element.as_node().clone(),
+                        original_content: String::from(
+                            element.attributes.borrow().get("href").unwrap(),
+                        ),
+                        attribute: String::from("href"),
+                        format: String::from(":rel-from:content"),
+                    })
+                });
+        }
+
+        // Canonical links can point every page at the listing of every item instead,
+        // as long as it wasn't skipped for writing over another container's listing
+        let canonical_format = if self.canonical_all && self.all_listing().is_some() {
+            ":content:rel-all"
+        } else {
+            ":content:rel-to"
+        };
+        if let Ok(select) = document.select("[rel=\"canonical\"]") {
+            select.for_each(|element| {
+                self.changes.push(PagebreakChange::Attribute {
+                    node: element.as_node().clone(),
+                    original_content: String::from(
+                        element.attributes.borrow().get("href").unwrap(),
+                    ),
+                    attribute: String::from("href"),
+                    format: String::from(canonical_format),
+                })
+            });
+        }
+
+        if let Ok(select) = document.select("[property=\"og:url\"]") {
+            select.for_each(|element| {
+                self.changes.push(PagebreakChange::Attribute {
+                    node: element.as_node().clone(),
+                    original_content: String::from(
+                        element.attributes.borrow().get("content").unwrap(),
+                    ),
+                    attribute: String::from("content"),
+                    format: String::from(":content:rel-to"),
+                })
+            });
+        }
+    }
+
+    pub fn apply_changes(&self, page_index: usize) {
+        // The first page keeps its original content, other than links to the listing of every item
+        let first_page = page_index == 0 && self.listing.is_none();
+        // The listing of every item isn't one page of many, so its titles aren't numbered
+        let page_format = |format: &String| -> String {
+            if self.shows_all() && *format == self.page_meta_format {
+                ":content".to_string()
+            } else {
+                format.clone()
+            }
+        };
+
+        for change in self
+            .changes
+            .iter()
+            .filter(|change| !first_page || change.format().contains(":rel-all"))
+        {
+            match change {
+                PagebreakChange::Content {
+                    node,
+                    original_content,
+                    format,
+                } => {
+                    let resolved_content =
+                        self.resolve_format(&page_format(format), page_index, original_content);
+
+                    node.children().for_each(|child| child.detach());
+                    node.append(NodeRef::new_text(&resolved_content))
+                }
+                PagebreakChange::Attribute {
+                    node,
+                    attribute,
+                    original_content,
+                    format,
+                } => {
+                    let mut attributes = node.as_element().unwrap().attributes.borrow_mut();
+                    let attribute = &attribute[..];
+
+                    if attributes.get(attribute).is_some() {
+                        let resolved_content =
+                            self.resolve_format(&page_format(format), page_index, original_content);
+                        attributes.remove(attribute);
+                        attributes.insert(attribute, resolved_content);
+                    }
+                }
+            }
+        }
+    }
+
+    fn resolve_format(&self, format: &str, page_index: usize, content: &str) -> String {
+        let file_url = self.get_file_url(page_index).unwrap();
+        let path_to = relative_path_between_files(&self.source_path, &file_url);
+        let path_from = relative_path_between_files(&file_url, &self.source_path);
+        let path_all = self
+            .all_listing()
+            .and_then(|listing| self.get_listing_url(listing).ok())
+            .map(|all_url| relative_path_between_files(&self.source_path, &all_url))
+            .unwrap_or_default();
+
+        let format = self
+            .format_counts(page_index)
+            .into_iter()
+            .fold(format.to_string(), |format, (token, count)| {
+                format.replace(token, &count.to_string())
+            });
+
+        format
+            .replace(":key", &self.page_key(page_index))
+            .replace(":tag", self.current_tag())
+            .replace(":content", content)
+            .replace(":rel-from", &path_from)
+            .replace(":rel-to", &path_to)
+            .replace(":rel-all", &path_all)
+    }
+
+    /// The numeric tokens available to formats on the given page
+    fn format_counts(&self, page_index: usize) -> [(&'static str, usize); 5] {
+        let (first_item, last_item) = self.item_range(page_index);
+        [
+            (":num", self.page_number(page_index)),
+            (":total", self.page_count),
+            (":items", self.item_count()),
+            (":first", first_item),
+            (":last", last_item),
+        ]
+    }
+
+    /// Resolves a label format, first choosing between any `|` separated plural forms.
+    /// Forms are chosen by the first count found in the last form that contains one,
+    /// either as `one|many` or as `zero|one|many`. A literal `|` is written as `||`
+    fn resolve_label(&self, format: &str, page_index: usize) -> String {
+        // Escaped separators are swapped out while splitting the forms
+        let forms: Vec<String> = format
+            .replace("||", "\0")
+            .split('|')
+            .map(|form| form.replace('\0', "|"))
+            .collect();
+        let counts = self.format_counts(page_index);
+        let count = forms.iter().rev().find_map(|form| {
+            counts
+                .iter()
+                .filter_map(|(token, count)| form.find(token).map(|position| (position, count)))
+                .min()
+                .map(|(_, count)| *count)
+        });
+        let form = match (forms.len(), count) {
+            (2, Some(1)) | (3, Some(0)) => &forms[0],
+            (3, Some(1)) => &forms[1],
+            _ => &forms[forms.len() - 1],
+        };
+        self.resolve_format(form, page_index, "")
+    }
+
+    /// Swaps the items in every container of this group for those on the given page
+    pub fn show_page(&self, page_index: usize) {
+        self.containers
+            .iter()
+            .for_each(|container| container.show_page(page_index));
+    }
+
+    fn get_file_url(&self, page_number: usize) -> Result<PathBuf, errors::PageError> {
+        match (page_number, self.listing) {
+            (0, None) => Ok(PathBuf::from(&self.file_path)),
+            (0, Some(listing)) => self.get_listing_url(listing),
+            _ => {
+                // Listings hold the same page sequence within their own directory
+                let listing = self
+                    .listing
+                    .map(|listing| self.listings[listing].path.as_str())
+                    .unwrap_or(".");
+                self.resolve_file_url(
+                    PathBuf::from(listing).join(
+                        self.page_url_format
+                            .replace(":num", &self.page_number(page_number).to_string())
+                            .replace(":key", &self.page_key(page_number)),
+                    ),
+                )
+            }
+        }
+    }
+
+    /// The URL of the first page of the given listing
+    fn get_listing_url(&self, listing: usize) -> Result<PathBuf, errors::PageError> {
+        self.resolve_file_url(PathBuf::from(&self.listings[listing].path))
+    }
+
+    /// Resolves a page URL relative to the original file into an output path
+    fn resolve_file_url(&self, file_url: PathBuf) -> Result<PathBuf, errors::PageError> {
+        let file_stem = self.file_path.file_stem().unwrap().to_str().unwrap();
+        let file_path = if !file_stem.eq("index") {
+            PathBuf::from(file_stem).join(file_url).join("index.html")
+        } else {
+            file_url.join("index.html")
+        };
+        let cleaned_path = self.file_path.parent().unwrap().join(file_path).lexiclean();
+        match cleaned_path.components().next().unwrap() {
+            Component::ParentDir => Err(errors::PageError {
+                code: errors::PageErrorCode::ParentDir,
+                relative_path: self.file_path.to_str().unwrap().to_string(),
+                message: format!(
+                    "Pagination URL resolves outside of output directory: {:?}",
+                    cleaned_path
+                ),
+            }),
+            _ => Ok(cleaned_path),
+        }
+    }
+}
+
+/// The containers nested within an item of a container, paginated as a sub-series
+/// of whichever page of the parent group shows that item
+struct PagebreakNested {
+    group: usize,
+    scope: NodeRef,
+    state: PagebreakState,
+}
+
+impl PagebreakNested {
+    fn is_shown(&self, document: &NodeRef) -> bool {
+        self.scope.ancestors().last().as_ref() == Some(document)
+    }
+}
+
+pub struct PagebreakState {
+    pub document: NodeRef,
+    /// The part of the document searched for containers and controls
+    scope: NodeRef,
+    file_path: PathBuf,
+    output_path: PathBuf,
+    groups: Vec<PagebreakGroup>,
+    nested: Vec<PagebreakNested>,
+    pagebreak_elements: Option<Vec<PagebreakElement>>,
+    generated_elements: Vec<NodeRef>,
+}
+
+impl PagebreakState {
+    pub fn new(document: NodeRef, file_path: PathBuf, output_path: PathBuf) -> Self {
+        PagebreakState {
+            scope: document.clone(),
+            document,
+            file_path,
+            output_path,
+            groups: Vec::default(),
+            nested: Vec::default(),
+            pagebreak_elements: None,
+            generated_elements: Vec::default(),
+        }
+    }
+
+    fn is_nested(&self) -> bool {
+        self.scope != self.document
+    }
+
+    pub fn hydrate(&mut self) {
+        self.find_pagebreak_nodes();
+        if !self.groups.is_empty() {
+            self.groups.iter_mut().for_each(|group| group.hydrate());
+            self.remove_conflicting_groups();
+            self.remove_conflicting_listings();
+            if !self.is_nested() {
+                self.find_nested_states();
+            }
+            self.find_pagebreak_elements();
+            let document = self.document.clone();
+            self.groups
+                .iter_mut()
+                .for_each(|group| group.find_changes(&document));
+        }
+    }
+
+    pub fn paginate(&mut self) {
+        if self.groups.is_empty() {
+            return;
+        };
+
+        // The original file holds the first page of every group
+        self.groups
+            .iter()
+            .chain(self.nested.iter().flat_map(|nested| &nested.state.groups))
+            .for_each(|group| group.show_page(0));
+        self.write_page(0, 0, &mut |_| {});
+        self.write_nested_pages(None, 0);
+
+        self.write_series(&mut |_| {});
+    }
+
+    /// Writes every page after the first of each group, along with each of their listings.
+    /// Before each page is written, `parent` is given its URL to update the parent's controls
+    fn write_series(&mut self, parent: &mut dyn FnMut(&Path)) {
+        for group_index in 0..self.groups.len() {
+            self.write_pages(group_index, 1, parent);
+
+            for listing in 0..self.groups[group_index].listings.len() {
+                if let Err(err) = self.groups[group_index].get_listing_url(listing) {
+                    eprintln!("{:?}\nPagebreak: Skipping errored listing", err);
+                    continue;
+                }
+                self.groups[group_index].show_listing(Some(listing));
+                self.write_pages(group_index, 0, parent);
+            }
+            if self.groups[group_index].listing.is_some() {
+                self.groups[group_index].show_listing(None);
+            }
+            self.groups[group_index].show_page(0);
+        }
+    }
+
+    /// Writes each page of the given group's current listing, from `first_page` onwards
+    fn write_pages(
+        &mut self,
+        group_index: usize,
+        first_page: usize,
+        parent: &mut dyn FnMut(&Path),
+    ) {
+        // Pages sharing a URL, such as a key split across pages without `:num`, are only written once
+        let group = &self.groups[group_index];
+        let mut written_urls: Vec<PathBuf> = (0..first_page)
+            .filter_map(|page| group.get_file_url(page).ok())
+            .collect();
+        for page_number in first_page..self.groups[group_index].page_count {
+            let file_url = match self.groups[group_index].get_file_url(page_number) {
+                Ok(file_url) => file_url,
+                Err(err) => {
+                    eprintln!("{:?}\nPagebreak: Skipping errored page", err);
+                    break;
+                }
+            };
+            if written_urls.contains(&file_url) {
+                let err = errors::PageError {
+                    code: errors::PageErrorCode::DuplicateUrl,
+                    relative_path: self.file_path.to_str().unwrap().to_string(),
+                    message: format!(
+                        "Multiple pages use the pagination URL {:?}, include :num in data-pagebreak-url",
+                        file_url
+                    ),
+                };
+                eprintln!("{:?}\nPagebreak: Skipping errored page", err);
+                continue;
+            }
+            written_urls.push(file_url);
+            self.groups[group_index].show_page(page_number);
+            self.write_page(group_index, page_number, parent);
+            self.write_nested_pages(Some(group_index), page_number);
+        }
+    }
+
+    /// Writes the document with the given group on the given page,
+    /// and every other group on its first page
+    fn write_page(&mut self, group_index: usize, page_index: usize, parent: &mut dyn FnMut(&Path)) {
+        let group = &self.groups[group_index];
+        let file_url = group
+            .get_file_url(page_index)
+            .expect("Page URL was checked");
+
+        parent(&file_url);
+        self.update_elements_for_page(group_index, page_index, &file_url);
+        self.groups[group_index].apply_changes(page_index);
+
+        let output_file_path = self.output_path.join(file_url);
+        fs::create_dir_all(&output_file_path.parent().unwrap()).unwrap();
+        self.write_current_document_to_disk(output_file_path);
+
+        self.reattach_elements();
+    }
+
+    /// Writes the sub-series of the nested containers currently shown, within the given group
+    /// on the given page, or within every group on its first page
+    fn write_nested_pages(&mut self, active_group: Option<usize>, active_page: usize) {
+        // Each sub-series must not write over the pages of the parent or of another sub-series
+        let mut written_urls = self.series_urls();
+        for index in 0..self.nested.len() {
+            let nested = &self.nested[index];
+            if active_group.is_some_and(|group| group != nested.group)
+                || !nested.is_shown(&self.document)
+            {
+                continue;
+            }
+            let Ok(file_url) = self.groups[nested.group].get_file_url(active_page) else {
+                continue;
+            };
+
+            let mut nested = self.nested.remove(index);
+            nested.state.set_file_path(&file_url);
+            let urls = nested.state.series_urls();
+            if let Some(url) = urls.iter().find(|url| written_urls.contains(url)) {
+                let err = errors::PageError {
+                    code: errors::PageErrorCode::DuplicateUrl,
+                    relative_path: self.file_path.to_str().unwrap().to_string(),
+                    message: format!(
+                        "A nested container on {:?} would write over {:?}, give each nested container its own data-pagebreak-url",
+                        file_url, url
+                    ),
+                };
+                eprintln!("{:?}\nPagebreak: Skipping errored container", err);
+                self.nested.insert(index, nested);
+                continue;
+            }
+            written_urls.extend(urls);
+
+            nested.state.write_series(&mut |file_url| {
+                self.reattach_elements();
+                self.update_elements_for_page(active_group.unwrap_or(0), active_page, file_url);
+            });
+            nested
+                .state
+                .groups
+                .iter()
+                .for_each(|group| group.show_page(0));
+            self.reattach_elements();
+            self.nested.insert(index, nested);
+        }
+    }
+
+    /// The URLs of every page after the first in each group's current listing
+    fn series_urls(&self) -> Vec<PathBuf> {
+        self.groups
+            .iter()
+            .flat_map(|group| {
+                (1..group.page_count).filter_map(move |page| group.get_file_url(page).ok())
+            })
+            .collect()
+    }
+
+    /// Nested containers paginate from the page of their parent that shows them
+    fn set_file_path(&mut self, file_path: &Path) {
+        self.groups
+            .iter_mut()
+            .for_each(|group| group.file_path = file_path.to_path_buf());
+    }
+
+    /// Finds the items of each container that hold containers of their own
+    fn find_nested_states(&mut self) {
+        let mut nested = vec![];
+        for (group_index, group) in self.groups.iter().enumerate() {
+            let elements = group
+                .containers
+                .iter()
+                .flat_map(|container| container.items.iter().chain(&container.filtered_items))
+                .flat_map(|item| item.elements());
+            for element in elements {
+                let mut state = PagebreakState::new(
+                    self.document.clone(),
+                    self.file_path.clone(),
+                    self.output_path.clone(),
+                );
+                state.scope = element.clone();
+                state.hydrate();
+                if !state.groups.is_empty() {
+                    nested.push(PagebreakNested {
+                        group: group_index,
+                        scope: element.clone(),
+                        state,
+                    });
+                }
+            }
+        }
+        self.nested = nested;
+    }
+
+    /// Containers sharing a `data-pagebreak-group` name are gathered into a single group,
+    /// all other containers form a group of their own.
+    /// Containers within another container are left to be paginated by their parent's items
+    fn find_pagebreak_nodes(&mut self) {
+        let mut groups: Vec<PagebreakGroup> = vec![];
+        let scope = self.scope.clone();
+        let is_container = |node: &NodeRef| {
+            node.as_element().is_some_and(|element| {
+                let attributes = element.attributes.borrow();
+                attributes.contains("data-pagebreak")
+                    || attributes.contains("data-pagebreak-article")
+            })
+        };
+        let (nodes, inner_nodes): (Vec<_>, Vec<_>) = self
+            .scope
+            .select("[data-pagebreak], [data-pagebreak-article]")
+            .unwrap()
+            .partition(|node| {
+                !node
+                    .as_node()
+                    .ancestors()
+                    .take_while(|ancestor| *ancestor != scope)
+                    .any(|ancestor| is_container(&ancestor))
+            });
+
+        // Only one level of nesting is paginated, so deeper containers are left as plain markup
+        if self.is_nested() && !inner_nodes.is_empty() {
+            eprintln!(
+                "Pagebreak: Containers nested more than one level deep on {:?} aren't supported, leaving them unpaginated",
+                self.file_path
+            );
+            for node in &inner_nodes {
+                let mut attributes = node.attributes.borrow_mut();
+                let names: Vec<_> = attributes
+                    .map
+                    .keys()
+                    .filter(|name| name.local.starts_with("data-pagebreak"))
+                    .cloned()
+                    .collect();
+                names.iter().for_each(|name| {
+                    attributes.map.remove(name);
+                });
+            }
+        }
+
+        nodes.into_iter().for_each(|node| {
+            let container = PagebreakContainer::new(node);
+            let name = container.take_attribute("data-pagebreak-group");
+            let existing_group = groups
+                .iter_mut()
+                .find(|group| name.is_some() && group.name == name);
+
+            match existing_group {
+                Some(group) => group.containers.push(container),
+                None => {
+                    let mut group = PagebreakGroup::new(name, self.file_path.clone());
+                    group.containers.push(container);
+                    groups.push(group);
+                }
+            }
+        });
+        self.groups = groups;
+    }
+
+    /// Groups that would write to the same URLs as an earlier group are left unpaginated
+    fn remove_conflicting_groups(&mut self) {
+        let mut url_formats: Vec<String> = vec![];
+        self.groups.retain(|group| {
+            if url_formats.contains(&group.page_url_format) {
+                let err = errors::PageError {
+                    code: errors::PageErrorCode::DuplicateUrl,
+                    relative_path: group.file_path.to_str().unwrap().to_string(),
+                    message: format!(
+                        "Multiple containers use the pagination URL {:?}, give each container its own data-pagebreak-url",
+                        group.page_url_format
+                    ),
+                };
+                eprintln!("{:?}\nPagebreak: Skipping errored container", err);
+                false
+            } else {
+                url_formats.push(group.page_url_format.clone());
+                true
+            }
+        });
+    }
+
+    /// Listings that would write to the same URL as a listing of an earlier group are skipped
+    fn remove_conflicting_listings(&mut self) {
+        let mut listing_urls: Vec<PathBuf> = vec![];
+        for group in self.groups.iter_mut() {
+            let relative_path = group.file_path.to_str().unwrap().to_string();
+            let urls: Vec<Option<PathBuf>> = (0..group.listings.len())
+                .map(|listing| group.get_listing_url(listing).ok())
+                .collect();
+            let mut urls = urls.into_iter();
+            group.listings.retain(|listing| {
+                // Errored URLs are reported when the listing is written
+                let Some(url) = urls.next().flatten() else {
+                    return true;
+                };
+                if listing_urls.contains(&url) {
+                    let err = errors::PageError {
+                        code: errors::PageErrorCode::DuplicateUrl,
+                        relative_path: relative_path.clone(),
+                        message: format!(
+                            "Multiple containers write a listing to {:?}, give each container its own {}",
+                            url,
+                            listing.url_attribute()
+                        ),
+                    };
+                    eprintln!("{:?}\nPagebreak: Skipping errored listing", err);
+                    false
+                } else {
+                    listing_urls.push(url);
+                    true
+                }
+            });
+        }
+    }
+
+    fn find_pagebreak_elements(&mut self) {
+        let mut elements = vec![];
+        self.scope
+            .select("[data-pagebreak-control], [data-pagebreak-label]")
+            .unwrap()
+            // Controls within a nested container's item belong to that container
+            .filter(|element| {
+                !element
+                    .as_node()
+                    .ancestors()
+                    .any(|ancestor| self.nested.iter().any(|nested| nested.scope == ancestor))
+            })
+            .for_each(|element| {
+                let element_node = element.as_node();
+                let mut element_attributes =
+                    element_node.as_element().unwrap().attributes.borrow_mut();
+                let group = match element_attributes.get("data-pagebreak-for") {
+                    Some(id) => {
+                        let group = self.groups.iter().position(|group| group.matches(id));
+                        if group.is_none() {
+                            eprintln!(
+                                "Pagebreak: No container with the id {:?} found on {:?}",
+                                id, self.file_path
+                            );
+                        }
+                        group
+                    }
+                    None => Some(0),
+                };
+                element_attributes.remove("data-pagebreak-for");
+                let (attribute, value) =
+                    if let Some(control) = element_attributes.get("data-pagebreak-control") {
+                        ("data-pagebreak-control", control)
+                    } else if let Some(label) = element_attributes.get("data-pagebreak-label") {
+                        ("data-pagebreak-label", label)
+                    } else {
                         unreachable!("Couldn't get attribute")
                     };
                 let element_type = match (attribute, value) {
@@ -384,263 +1958,1340 @@ impl PagebreakState {
                     ("data-pagebreak-control", "prev") => PagebreakElementType::Previous,
                     ("data-pagebreak-control", "!next") => PagebreakElementType::NoNext,
                     ("data-pagebreak-control", "!prev") => PagebreakElementType::NoPrevious,
+                    ("data-pagebreak-control", "first") => PagebreakElementType::First,
+                    ("data-pagebreak-control", "last") => PagebreakElementType::Last,
+                    ("data-pagebreak-control", "!first") => PagebreakElementType::NoFirst,
+                    ("data-pagebreak-control", "!last") => PagebreakElementType::NoLast,
+                    ("data-pagebreak-control", "pages") => PagebreakElementType::Pages,
+                    ("data-pagebreak-control", "ellipsis") => PagebreakElementType::Ellipsis,
+                    ("data-pagebreak-control", "tags") => PagebreakElementType::Tags,
+                    ("data-pagebreak-control", "overflow") => PagebreakElementType::Overflow,
+                    ("data-pagebreak-control", "all") => PagebreakElementType::All,
                     ("data-pagebreak-label", "current") => PagebreakElementType::Current,
                     ("data-pagebreak-label", "total") => PagebreakElementType::Total,
+                    ("data-pagebreak-label", "first-item") => PagebreakElementType::FirstItem,
+                    ("data-pagebreak-label", "last-item") => PagebreakElementType::LastItem,
+                    ("data-pagebreak-label", "total-items") => PagebreakElementType::TotalItems,
+                    ("data-pagebreak-label", format) if format.contains(':') => {
+                        PagebreakElementType::Format(format.to_string())
+                    }
                     _ => PagebreakElementType::None,
                 };
-                element_attributes.remove(attribute);
-                elements.push(PagebreakElement::new(
-                    element_node.clone(),
-                    element_type,
-                    element_node.parent(),
-                    element_node.previous_sibling(),
-                ));
+                element_attributes.remove(attribute);
+                elements.push(PagebreakElement::new(
+                    element_node.clone(),
+                    element_type,
+                    group,
+                    element_node.parent(),
+                    element_node.previous_sibling(),
+                ));
+            });
+        self.pagebreak_elements = Some(elements);
+    }
+
+    /// Updates the controls and labels of every group for a document written to `file_url`,
+    /// where the active group is on the given page and all others are on their first page
+    fn update_elements_for_page(
+        &mut self,
+        active_group: usize,
+        active_page: usize,
+        file_url: &Path,
+    ) {
+        for group_index in 0..self.groups.len() {
+            let group = &self.groups[group_index];
+            let page_index = if group_index == active_group {
+                active_page
+            } else {
+                0
+            };
+            let page_count = group.page_count;
+            let relative_href = |page: usize| {
+                relative_path_between_files(file_url, &group.get_file_url(page).unwrap())
+            };
+
+            self.update_element_text(
+                group_index,
+                PagebreakElementType::Current,
+                group.page_number(page_index).to_string(),
+            );
+            self.update_element_text(
+                group_index,
+                PagebreakElementType::Total,
+                page_count.to_string(),
+            );
+
+            let (first_item, last_item) = group.item_range(page_index);
+            self.update_element_text(
+                group_index,
+                PagebreakElementType::FirstItem,
+                first_item.to_string(),
+            );
+            self.update_element_text(
+                group_index,
+                PagebreakElementType::LastItem,
+                last_item.to_string(),
+            );
+            self.update_element_text(
+                group_index,
+                PagebreakElementType::TotalItems,
+                group.item_count().to_string(),
+            );
+            self.update_formatted_labels(group_index, page_index);
+
+            if page_index == 0 {
+                self.detach_element(group_index, PagebreakElementType::Previous);
+            } else {
+                self.update_element_href(
+                    group_index,
+                    PagebreakElementType::Previous,
+                    relative_href(page_index - 1),
+                );
+                self.detach_element(group_index, PagebreakElementType::NoPrevious);
+            }
+
+            if page_index + 1 >= page_count {
+                self.detach_element(group_index, PagebreakElementType::Next);
+            } else {
+                self.update_element_href(
+                    group_index,
+                    PagebreakElementType::Next,
+                    relative_href(page_index + 1),
+                );
+                self.detach_element(group_index, PagebreakElementType::NoNext);
+            }
+
+            if page_index == 0 {
+                self.detach_element(group_index, PagebreakElementType::First);
+            } else {
+                self.update_element_href(
+                    group_index,
+                    PagebreakElementType::First,
+                    relative_href(0),
+                );
+                self.detach_element(group_index, PagebreakElementType::NoFirst);
+            }
+
+            if page_index + 1 >= page_count {
+                self.detach_element(group_index, PagebreakElementType::Last);
+            } else {
+                self.update_element_href(
+                    group_index,
+                    PagebreakElementType::Last,
+                    relative_href(page_count - 1),
+                );
+                self.detach_element(group_index, PagebreakElementType::NoLast);
+            }
+
+            // Items left out of the series are linked to from its final page
+            if page_index + 1 < page_count || !group.overflows() {
+                self.detach_element(group_index, PagebreakElementType::Overflow);
+            }
+
+            // The listing of every item doesn't link to itself
+            match group
+                .all_listing()
+                .filter(|_| !group.shows_all())
+                .and_then(|listing| group.get_listing_url(listing).ok())
+            {
+                Some(all_url) => self.update_element_href(
+                    group_index,
+                    PagebreakElementType::All,
+                    relative_path_between_files(file_url, &all_url),
+                ),
+                None => self.detach_element(group_index, PagebreakElementType::All),
+            }
+
+            self.update_page_list(group_index, page_index, file_url);
+            self.update_tag_list(group_index, file_url);
+        }
+
+        // Nested containers that are shown stay on their first page
+        for nested in self.nested.iter_mut() {
+            if !nested.is_shown(&self.document) {
+                continue;
+            }
+            let group = &self.groups[nested.group];
+            let page_index = if nested.group == active_group {
+                active_page
+            } else {
+                0
+            };
+            if let Ok(parent_url) = group.get_file_url(page_index) {
+                nested.state.set_file_path(&parent_url);
+                nested.state.update_elements_for_page(0, 0, file_url);
+            }
+        }
+    }
+
+    /// Replaces each `pages` template with a clone per listed page, separated by clones
+    /// of a sibling `ellipsis` template wherever pages are skipped
+    fn update_page_list(&mut self, group_index: usize, page_index: usize, file_url: &Path) {
+        let group = &self.groups[group_index];
+        let mut generated = vec![];
+
+        for template in self.elements_of_type(group_index, PagebreakElementType::Pages) {
+            let ellipsis = self
+                .elements_of_type(group_index, PagebreakElementType::Ellipsis)
+                .find(|ellipsis| ellipsis.parent == template.parent)
+                .map(|ellipsis| &ellipsis.element);
+            let template = &template.element;
+            let window = {
+                let mut attributes = template.as_element().unwrap().attributes.borrow_mut();
+                let window = attributes
+                    .get("data-pagebreak-window")
+                    .map(|window| parse_number("data-pagebreak-window", window));
+                if window == Some(None) {
+                    // Only warn about an invalid window once, rather than on every page
+                    attributes.remove("data-pagebreak-window");
+                }
+                window.flatten().unwrap_or(2)
+            };
+            let separator = template
+                .previous_sibling()
+                .and_then(|sibling| sibling.as_text().map(|text| text.borrow().clone()))
+                .filter(|text| text.trim().is_empty());
+
+            let mut previous = template.clone();
+            for (index, entry) in page_list(page_index, group.page_count, window)
+                .into_iter()
+                .enumerate()
+            {
+                if let Some(separator) = separator.as_ref().filter(|_| index > 0) {
+                    let separator = NodeRef::new_text(separator);
+                    previous.insert_after(separator.clone());
+                    generated.push(separator.clone());
+                    previous = separator;
+                }
+
+                let node = match (entry, ellipsis) {
+                    (Some(page), _) => {
+                        let node = deep_clone(template);
+                        node.as_element()
+                            .unwrap()
+                            .attributes
+                            .borrow_mut()
+                            .remove("data-pagebreak-window");
+                        let link = match node.select_first("[href]") {
+                            Ok(link) => link.as_node().clone(),
+                            Err(_) => node.clone(),
+                        };
+                        let mut attributes = link.as_element().unwrap().attributes.borrow_mut();
+                        if attributes.contains("href") {
+                            let href = relative_path_between_files(
+                                file_url,
+                                &group.get_file_url(page).unwrap(),
+                            );
+                            attributes.insert("href", href);
+                        }
+                        if page == page_index {
+                            attributes.insert("aria-current", "page".to_string());
+                        }
+                        link.children().for_each(|child| child.detach());
+                        link.append(NodeRef::new_text(group.page_number(page).to_string()));
+                        node
+                    }
+                    (None, Some(ellipsis)) => deep_clone(ellipsis),
+                    (None, None) => NodeRef::new_text("…"),
+                };
+                previous.insert_after(node.clone());
+                generated.push(node.clone());
+                previous = node;
+            }
+        }
+
+        self.generated_elements.extend(generated);
+        self.detach_element(group_index, PagebreakElementType::Pages);
+        self.detach_element(group_index, PagebreakElementType::Ellipsis);
+    }
+
+    fn update_formatted_labels(&self, group_index: usize, page_index: usize) {
+        let group = &self.groups[group_index];
+        self.pagebreak_elements
+            .as_ref()
+            .unwrap()
+            .iter()
+            .filter(|element| element.group == Some(group_index))
+            .for_each(|element| {
+                if let PagebreakElementType::Format(format) = &element.element_type {
+                    let node_ref = &element.element;
+                    node_ref.children().for_each(|child| child.detach());
+                    node_ref.append(NodeRef::new_text(group.resolve_label(format, page_index)));
+                }
             });
-        self.pagebreak_elements = Some(elements);
     }
 
-    fn resolve_format(&self, format: &str, page_index: usize, content: &str) -> String {
-        let path_to = self.relative_path_between_pages(0, page_index);
-        let path_from = self.relative_path_between_pages(page_index, 0);
+    /// Replaces each `tags` template with a clone linking to the listing of each tag
+    fn update_tag_list(&mut self, group_index: usize, file_url: &Path) {
+        let group = &self.groups[group_index];
+        let mut generated = vec![];
+
+        for template in self.elements_of_type(group_index, PagebreakElementType::Tags) {
+            let template = &template.element;
+            let separator = template
+                .previous_sibling()
+                .and_then(|sibling| sibling.as_text().map(|text| text.borrow().clone()))
+                .filter(|text| text.trim().is_empty());
+
+            let mut previous = template.clone();
+            for (index, (listing, tag)) in group.tags().enumerate() {
+                if let Some(separator) = separator.as_ref().filter(|_| index > 0) {
+                    let separator = NodeRef::new_text(separator);
+                    previous.insert_after(separator.clone());
+                    generated.push(separator.clone());
+                    previous = separator;
+                }
+
+                let node = deep_clone(template);
+                let link = match node.select_first("[href]") {
+                    Ok(link) => link.as_node().clone(),
+                    Err(_) => node.clone(),
+                };
+                let mut attributes = link.as_element().unwrap().attributes.borrow_mut();
+                if attributes.contains("href") {
+                    if let Ok(listing_url) = group.get_listing_url(listing) {
+                        let href = relative_path_between_files(file_url, &listing_url);
+                        attributes.insert("href", href);
+                    }
+                }
+                if group.listing == Some(listing) {
+                    attributes.insert("aria-current", "page".to_string());
+                }
+                link.children().for_each(|child| child.detach());
+                link.append(NodeRef::new_text(tag));
+
+                previous.insert_after(node.clone());
+                generated.push(node.clone());
+                previous = node;
+            }
+        }
+
+        self.generated_elements.extend(generated);
+        self.detach_element(group_index, PagebreakElementType::Tags);
+    }
+
+    fn elements_of_type(
+        &self,
+        group_index: usize,
+        element_type: PagebreakElementType,
+    ) -> impl Iterator<Item = &PagebreakElement> {
+        self.pagebreak_elements
+            .as_ref()
+            .unwrap()
+            .iter()
+            .filter(move |element| {
+                element.group == Some(group_index) && element.element_type == element_type
+            })
+    }
+
+    fn detach_element(&self, group_index: usize, element_type: PagebreakElementType) {
+        self.elements_of_type(group_index, element_type)
+            .for_each(|element| {
+                element.element.detach();
+            });
+    }
+
+    fn reattach_elements(&mut self) {
+        self.nested
+            .iter_mut()
+            .for_each(|nested| nested.state.reattach_elements());
+        self.generated_elements
+            .drain(..)
+            .for_each(|element| element.detach());
+        self.pagebreak_elements
+            .as_ref()
+            .unwrap()
+            .iter()
+            .for_each(|element| {
+                if element.previous_sibling.is_some() {
+                    element
+                        .previous_sibling
+                        .as_ref()
+                        .unwrap()
+                        .insert_after(element.element.clone())
+                } else if element.parent.is_some() {
+                    element
+                        .parent
+                        .as_ref()
+                        .unwrap()
+                        .prepend(element.element.clone());
+                }
+            });
+    }
+
+    fn update_element_href(
+        &self,
+        group_index: usize,
+        element_type: PagebreakElementType,
+        new_href: String,
+    ) {
+        self.elements_of_type(group_index, element_type)
+            .for_each(|element| {
+                let mut attributes = element
+                    .element
+                    .as_element()
+                    .unwrap()
+                    .attributes
+                    .borrow_mut();
+                attributes.remove("href");
+                attributes.insert("href", new_href.clone());
+            });
+    }
+
+    fn update_element_text(
+        &self,
+        group_index: usize,
+        element_type: PagebreakElementType,
+        new_text: String,
+    ) {
+        self.elements_of_type(group_index, element_type)
+            .for_each(|element| {
+                let node_ref = &element.element;
+                node_ref.children().for_each(|child| child.detach());
+                node_ref.append(NodeRef::new_text(&new_text));
+            });
+    }
+
+    fn write_current_document_to_disk(&self, path: PathBuf) {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path).unwrap());
+        self.document.serialize(&mut file).unwrap();
+    }
+}
+
+fn relative_path_between_files(from_path: &Path, to_path: &Path) -> String {
+    let mut relative_path =
+        pathdiff::diff_paths(to_path.parent().unwrap(), from_path.parent().unwrap()).unwrap();
+    // relative_path.strip_prefix(base)
+    match relative_path.components().next() {
+        None => return "./".to_string(),
+        Some(Component::CurDir) => {
+            relative_path = relative_path
+                .strip_prefix(".")
+                .expect("Prefix was checked")
+                .to_path_buf();
+        }
+        _ => {}
+    }
+    format!(
+        "{}/",
+        relative_path
+            .to_str()
+            .expect("valid characters")
+            .replace("\\", "/")
+    )
+}
+
+/// Lists the pages to link in a numbered page list: the first and last pages, and `window`
+/// pages either side of the current page. Gaps between listed pages are marked with `None`.
+fn page_list(page_index: usize, page_count: usize, window: usize) -> Vec<Option<usize>> {
+    let mut pages = vec![];
+    let mut last_listed: Option<usize> = None;
+    for page in 0..page_count {
+        let listed = page == 0 || page + 1 == page_count || page.abs_diff(page_index) <= window;
+        if !listed {
+            continue;
+        }
+        match last_listed {
+            // A gap of a single page is cheaper to show than an ellipsis
+            Some(last) if page - last == 2 => pages.push(Some(last + 1)),
+            Some(last) if page - last > 2 => pages.push(None),
+            _ => {}
+        }
+        pages.push(Some(page));
+        last_listed = Some(page);
+    }
+    pages
+}
+
+/// Parses the number given to an attribute, warning and ignoring the attribute if it isn't one
+fn parse_number(attribute: &str, value: &str) -> Option<usize> {
+    match value.trim().parse::<usize>() {
+        Ok(number) => Some(number),
+        Err(_) => {
+            eprintln!(
+                "Pagebreak: Invalid {} {:?}, expected a whole number",
+                attribute, value
+            );
+            None
+        }
+    }
+}
+
+/// Lowercases a tag and replaces anything but letters and numbers with dashes
+fn slugify(tag: &str) -> String {
+    tag.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Copies a node without its children, detached from the document
+fn shallow_clone(node: &NodeRef) -> NodeRef {
+    let mut data = node.data().clone();
+    if let NodeData::Element(element) = &mut data {
+        element.template_contents = element.template_contents.as_ref().map(deep_clone);
+    }
+    NodeRef::new(data)
+}
+
+/// Copies a node and all of its descendants, detached from the document
+fn deep_clone(node: &NodeRef) -> NodeRef {
+    let clone = shallow_clone(node);
+    node.children()
+        .for_each(|child| clone.append(deep_clone(&child)));
+    clone
+}
+
+pub trait PagebreakStatusLogging {
+    fn log_hydrated(&self);
+}
+
+impl PagebreakStatusLogging for PagebreakState {
+    fn log_hydrated(&self) {
+        self.groups.iter().for_each(|group| {
+            group.containers.iter().for_each(|container| {
+                let page_size = if container.per_page == usize::MAX {
+                    "split at break markers".to_string()
+                } else {
+                    format!("of size {}", container.per_page)
+                };
+                println!(
+                    "Pagebreak: Found {} items on {:?}; Building {} pages {}",
+                    container.items.len(),
+                    self.file_path,
+                    group.page_count,
+                    page_size
+                );
+            });
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kuchiki::traits::TendrilSink;
+
+    use super::*;
+
+    fn new_group() -> PagebreakGroup {
+        PagebreakGroup::new(None, PathBuf::from("index.html"))
+    }
+
+    fn hydrated_state(html: &str) -> PagebreakState {
+        let mut state = PagebreakState::new(
+            kuchiki::parse_html().one(html),
+            PathBuf::from("index.html"),
+            PathBuf::from("output"),
+        );
+        state.hydrate();
+        state
+    }
+
+    /// The text of the first container in the group, without the whitespace between its children
+    fn container_text(group: &PagebreakGroup) -> String {
+        group.containers[0]
+            .node
+            .as_node()
+            .text_contents()
+            .replace('\n', "")
+    }
+
+    #[test]
+    fn test_get_file_url() {
+        let mut group = new_group();
+        assert_eq!(PathBuf::from("index.html"), group.get_file_url(0).unwrap(),);
+        assert_eq!(
+            PathBuf::from("page/2/index.html"),
+            group.get_file_url(1).unwrap(),
+        );
+
+        group.file_path = PathBuf::from("about/index.html");
+        assert_eq!(
+            PathBuf::from("about/index.html"),
+            group.get_file_url(0).unwrap(),
+        );
+        assert_eq!(
+            PathBuf::from("about/page/2/index.html"),
+            group.get_file_url(1).unwrap(),
+        );
+
+        group.file_path = PathBuf::from("a/b/c/index.html");
+        group.page_url_format = "../../page/:num/".to_string();
+        assert_eq!(
+            PathBuf::from("a/page/2/index.html"),
+            group.get_file_url(1).unwrap(),
+        );
+    }
+
+    #[test]
+    fn test_bad_file_url() {
+        let mut group = new_group();
+        group.page_url_format = "../page/:num/".to_string();
+        assert_eq!(
+            errors::PageErrorCode::ParentDir,
+            group.get_file_url(1).unwrap_err().code,
+        );
+    }
+
+    #[test]
+    fn test_relative_pagination_urls() {
+        let relative_path_between_pages = |group: &PagebreakGroup, from: usize, to: usize| {
+            relative_path_between_files(
+                &group.get_file_url(from).unwrap(),
+                &group.get_file_url(to).unwrap(),
+            )
+        };
+        let mut group = new_group();
+        assert_eq!("page/2/", relative_path_between_pages(&group, 0, 1));
+        assert_eq!("../../", relative_path_between_pages(&group, 1, 0));
+        assert_eq!("../3/", relative_path_between_pages(&group, 1, 2));
+
+        group.file_path = PathBuf::from("file/main/index.html");
+        group.page_url_format = "../pages/:num/page/".to_string();
+        assert_eq!(
+            "../pages/2/page/",
+            relative_path_between_pages(&group, 0, 1)
+        );
+        assert_eq!("../../../main/", relative_path_between_pages(&group, 1, 0));
+        assert_eq!("../../3/page/", relative_path_between_pages(&group, 1, 2));
+
+        group.file_path = PathBuf::from("index.html");
+        group.page_url_format = "./:num/".to_string();
+        assert_eq!("2/", relative_path_between_pages(&group, 0, 1));
+        assert_eq!("../", relative_path_between_pages(&group, 1, 0));
+    }
+
+    #[test]
+    fn test_multiple_containers() {
+        let state = hydrated_state(
+            r#"
+            <section id="posts" data-pagebreak="1"><p>Post 1</p><p>Post 2</p></section>
+            <section id="events" data-pagebreak="2" data-pagebreak-url="./events/:num/"><p>Event 1</p><p>Event 2</p><p>Event 3</p></section>
+            <a href="" data-pagebreak-control="next" data-pagebreak-for="events">Next</a>
+            "#,
+        );
+
+        assert_eq!(2, state.groups.len());
+        assert_eq!(2, state.groups[0].page_count);
+        assert_eq!(2, state.groups[1].page_count);
+        assert_eq!(Some(1), state.pagebreak_elements.as_ref().unwrap()[0].group);
+    }
+
+    #[test]
+    fn test_page_list() {
+        assert_eq!(vec![Some(0)], page_list(0, 1, 2));
+        assert_eq!(
+            vec![Some(0), Some(1), Some(2), None, Some(19)],
+            page_list(0, 20, 2)
+        );
+        assert_eq!(
+            vec![Some(0), None, Some(6), Some(7), Some(8), None, Some(19)],
+            page_list(7, 20, 1)
+        );
+        assert_eq!(
+            vec![Some(0), Some(1), Some(2), Some(3), None, Some(19)],
+            page_list(2, 20, 1)
+        );
+    }
+
+    #[test]
+    fn test_resolve_label() {
+        let mut state =
+            hydrated_state(r#"<section data-pagebreak="2"><p></p><p></p><p></p></section>"#);
+        let group = &state.groups[0];
+
+        assert_eq!(
+            "Page 2 of 2 (3 items)",
+            group.resolve_label("Page :num of :total (:items items)", 1)
+        );
+        assert_eq!("2 pages", group.resolve_label("1 page|:total pages", 0));
+        assert_eq!(
+            "3 items",
+            group.resolve_label("No items|One item|:items items", 0)
+        );
+        assert_eq!(
+            "Showing 3-3",
+            group.resolve_label("Showing :first-:last", 1)
+        );
+        assert_eq!(
+            "Posts | Page 1 of 2",
+            group.resolve_label("Posts || Page :num of :total", 0)
+        );
+        assert_eq!(
+            "2 pages | Blog",
+            group.resolve_label("1 page || Blog|:total pages || Blog", 0)
+        );
+
+        state.groups[0].page_count = 1;
+        assert_eq!(
+            "1 page",
+            state.groups[0].resolve_label("1 page|:total pages", 0)
+        );
+    }
+
+    #[test]
+    fn test_first_page_size() {
+        let state = hydrated_state(
+            r#"<section data-pagebreak="3" data-pagebreak-first="1"><p></p><p></p><p></p><p></p><p></p><p></p></section>"#,
+        );
+        let group = &state.groups[0];
+
+        assert_eq!(3, group.page_count);
+        assert_eq!(vec![0..1, 1..4, 4..6], group.containers[0].pages);
+        assert_eq!((1, 1), group.item_range(0));
+        assert_eq!((2, 4), group.item_range(1));
+        assert_eq!((5, 6), group.item_range(2));
+    }
+
+    #[test]
+    fn test_key_pages() {
+        let state = hydrated_state(
+            r#"
+            <section data-pagebreak="2" data-pagebreak-url="./:key/:num/">
+                <p data-pagebreak-key="2024"></p><p data-pagebreak-key="2024"></p><p data-pagebreak-key="2024"></p>
+                <p data-pagebreak-key="2023"></p>
+            </section>
+            "#,
+        );
+        let group = &state.groups[0];
+
+        assert_eq!(vec![0..2, 2..3, 3..4], group.containers[0].pages);
+        assert_eq!("2024", group.page_key(1));
+        assert_eq!(
+            PathBuf::from("2023/3/index.html"),
+            group.get_file_url(2).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_weighted_pages() {
+        let state = hydrated_state(
+            r#"
+            <section data-pagebreak="4">
+                <p data-pagebreak-weight="2"></p><p></p><p></p><p data-pagebreak-weight="2"></p><p data-pagebreak-weight="6"></p><p></p>
+            </section>
+            <section data-pagebreak="5" data-pagebreak-measure="words" data-pagebreak-url="./words/:num/">
+                <p>one two three</p><p>four five</p><p>six</p><p>seven eight nine ten eleven twelve</p>
+            </section>
+            "#,
+        );
+
+        assert_eq!(
+            vec![0..3, 3..4, 4..5, 5..6],
+            state.groups[0].containers[0].pages
+        );
+        assert_eq!(vec![0..2, 2..3, 3..4], state.groups[1].containers[0].pages);
+    }
+
+    #[test]
+    fn test_orphans() {
+        let items = "<p></p>".repeat(41);
+        let state = hydrated_state(&format!(
+            r#"
+            <section data-pagebreak="10" data-pagebreak-orphans="3">{}</section>
+            <section data-pagebreak="10" data-pagebreak-orphans="3" data-pagebreak-url="./b/:num/">{}<p></p><p></p><p></p></section>
+            "#,
+            items, items
+        ));
+
+        assert_eq!(4, state.groups[0].page_count);
+        assert_eq!(30..41, state.groups[0].containers[0].page_range(3));
+        assert_eq!(5, state.groups[1].page_count);
+    }
+
+    #[test]
+    fn test_archive_orphans() {
+        let state = hydrated_state(&format!(
+            r#"<section data-pagebreak="5" data-pagebreak-orphans="3" data-pagebreak-archive>{}</section>"#,
+            "<p></p>".repeat(11)
+        ));
+        let group = &state.groups[0];
+
+        assert_eq!(2, group.page_count);
+        assert_eq!(vec![0..6, 6..11], group.containers[0].pages);
+        assert_eq!(
+            PathBuf::from("page/1/index.html"),
+            group.get_file_url(1).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_invalid_numbers() {
+        let state = hydrated_state(
+            r#"<section data-pagebreak="two" data-pagebreak-first="one" data-pagebreak-orphans="-1" data-pagebreak-max-pages="many"><p data-pagebreak-weight="wide"></p><p data-pagebreak-weight=" 2 "></p><p></p><p></p><p></p></section>"#,
+        );
+        let container = &state.groups[0].containers[0];
 
-        format
-            .replace(":num", &format!("{}", page_index + 1))
-            .replace(":content", content)
-            .replace(":rel-from", &path_from)
-            .replace(":rel-to", &path_to)
+        assert_eq!(2, container.per_page);
+        assert_eq!(None, container.first_page);
+        assert_eq!(0, container.orphans);
+        assert_eq!(None, container.max_pages);
+        assert_eq!(vec![0..1, 1..2, 2..4, 4..5], container.pages);
     }
 
-    fn update_elements_for_page(&mut self, page_index: usize, total_pages: usize) {
-        self.update_element_text(PagebreakElementType::Current, (page_index + 1).to_string());
-        self.update_element_text(PagebreakElementType::Total, total_pages.to_string());
+    #[test]
+    fn test_pinned_items() {
+        let state = hydrated_state(
+            r#"<section data-pagebreak="2"><p data-pagebreak-pin="bottom">Bottom</p><p>1</p><p>2</p><p data-pagebreak-pin="top">Top</p><p>3</p></section>"#,
+        );
+        let group = &state.groups[0];
 
-        if page_index == 0 {
-            self.detach_element(PagebreakElementType::Previous);
-        } else {
-            let relative_href = self.relative_path_between_pages(page_index, page_index - 1);
-            self.update_element_href(PagebreakElementType::Previous, relative_href);
-            self.detach_element(PagebreakElementType::NoPrevious);
-        }
+        assert_eq!(2, group.page_count);
+        assert_eq!(3, group.item_count());
 
-        if page_index == self.page_count.unwrap() - 1 {
-            self.detach_element(PagebreakElementType::Next);
-        } else {
-            let relative_href = self.relative_path_between_pages(page_index, page_index + 1);
-            self.update_element_href(PagebreakElementType::Next, relative_href);
-            self.detach_element(PagebreakElementType::NoNext);
-        }
+        group.show_page(1);
+        assert_eq!("Top3Bottom", container_text(group));
     }
 
-    fn detach_element(&mut self, element_type: PagebreakElementType) {
-        self.pagebreak_elements
-            .as_ref()
-            .unwrap()
-            .iter()
-            .filter(|element| element.element_type == element_type)
-            .for_each(|element| {
-                element.element.detach();
-            });
+    #[test]
+    fn test_static_items() {
+        let state = hydrated_state(
+            r#"<section data-pagebreak="2"><h2 data-pagebreak-static>H</h2><p>1</p><p>2</p><div data-pagebreak-static>Ad</div><p>3</p><p>4</p><p>5</p></section>"#,
+        );
+        let group = &state.groups[0];
+
+        assert_eq!(3, group.page_count);
+        assert_eq!(5, group.item_count());
+        group.show_page(1);
+        assert_eq!("H34Ad", container_text(group));
+        group.show_page(2);
+        assert_eq!("H5Ad", container_text(group));
     }
 
-    fn reattach_elements(&mut self) {
-        self.pagebreak_elements
-            .as_ref()
-            .unwrap()
-            .iter()
-            .for_each(|element| {
-                if element.previous_sibling.is_some() {
-                    element
-                        .previous_sibling
-                        .as_ref()
-                        .unwrap()
-                        .insert_after(element.element.clone())
-                } else if element.parent.is_some() {
-                    element
-                        .parent
-                        .as_ref()
-                        .unwrap()
-                        .prepend(element.element.clone());
-                }
-            });
+    #[test]
+    fn test_interstitial_inserts() {
+        let state = hydrated_state(
+            r#"<section data-pagebreak="3"><p>1</p><template data-pagebreak-insert-every="2"> <b>Ad</b> </template><p>2</p><p>3</p><p>4</p><p>5</p></section>"#,
+        );
+        let group = &state.groups[0];
+
+        assert_eq!(2, group.page_count);
+        assert_eq!(5, group.item_count());
+        group.show_page(0);
+        assert_eq!("12Ad3", container_text(group));
+        group.show_page(1);
+        assert_eq!("45Ad", container_text(group));
+        group.show_page(0);
+        assert_eq!("12Ad3", container_text(group));
     }
 
-    fn update_element_href(&mut self, element_type: PagebreakElementType, new_href: String) {
-        self.pagebreak_elements
-            .as_ref()
-            .unwrap()
-            .iter()
-            .filter(|element| element.element_type == element_type)
-            .for_each(|element| {
-                let mut attributes = element
-                    .element
-                    .as_element()
-                    .unwrap()
-                    .attributes
-                    .borrow_mut();
-                attributes.remove("href");
-                attributes.insert("href", new_href.clone());
-            });
+    #[test]
+    fn test_sorted_items() {
+        let mut state = hydrated_state(
+            r#"<section data-pagebreak="2" data-pagebreak-sort="data-date:desc" data-pagebreak-sorts="by-title=text"><p data-date="2023-01-02">B</p><p data-date="2023-03-01">C</p><p data-date="2022-12-01">A</p></section>"#,
+        );
+        let group = &mut state.groups[0];
+        group.show_page(0);
+        assert_eq!("CB", container_text(group));
+        assert_eq!(PathBuf::from("index.html"), group.get_file_url(0).unwrap());
+
+        group.show_listing(Some(0));
+        group.show_page(0);
+        assert_eq!("AB", container_text(group));
+        assert_eq!(
+            PathBuf::from("by-title/index.html"),
+            group.get_file_url(0).unwrap()
+        );
+        assert_eq!(
+            PathBuf::from("by-title/page/2/index.html"),
+            group.get_file_url(1).unwrap()
+        );
+
+        group.show_listing(None);
+        group.show_page(1);
+        assert_eq!("A", container_text(group));
+        assert_eq!(
+            PathBuf::from("page/2/index.html"),
+            group.get_file_url(1).unwrap()
+        );
     }
 
-    fn update_element_text(&mut self, element_type: PagebreakElementType, new_text: String) {
-        self.pagebreak_elements
-            .as_ref()
-            .unwrap()
+    #[test]
+    fn test_mixed_sort_values() {
+        let items: String = (0..200)
+            .map(|index| match index % 4 {
+                0 => format!(r#"<p data-v="{}">"#, index),
+                1 => format!(r#"<p data-v="{}a">"#, index),
+                2 => r#"<p data-v="NaN">"#.to_string(),
+                _ => r#"<p data-v="1a">"#.to_string(),
+            })
+            .collect();
+        let state = hydrated_state(&format!(
+            r#"<section data-pagebreak="10" data-pagebreak-sort="data-v">{}</section>"#,
+            items
+        ));
+        let container = &state.groups[0].containers[0];
+        let sort = container.sort.as_ref().unwrap();
+
+        let values: Vec<String> = container
+            .items
             .iter()
-            .filter(|element| element.element_type == element_type)
-            .for_each(|element| {
-                let node_ref = &element.element;
-                node_ref.children().for_each(|child| child.detach());
-                node_ref.append(NodeRef::new_text(&new_text));
-            });
+            .map(|item| sort.value(item))
+            .collect();
+        assert_eq!(200, values.len());
+        assert_eq!("0", values[0]);
+        assert_eq!("196", values[49]);
+        assert_eq!("NaN", values[50]);
+        assert_eq!("NaN", values[99]);
+        assert_eq!("101a", values[100]);
+        assert_eq!("9a", values[199]);
+        assert!(container
+            .items
+            .windows(2)
+            .all(|pair| sort.compare(&pair[0], &pair[1]) != Ordering::Greater));
     }
 
-    fn indent_for_next_element(&mut self) {
-        self.page_container
-            .as_ref()
-            .unwrap()
-            .as_node()
-            .append(NodeRef::new_text(&self.dom_indentation));
+    #[test]
+    fn test_tagged_listings() {
+        let mut state = hydrated_state(
+            r#"<section data-pagebreak="1"><p data-pagebreak-tags="Rust, Web Dev">1</p><p data-pagebreak-tags="web dev">2</p><p>3</p><p data-pagebreak-tags="Web Dev">4</p></section>"#,
+        );
+        let group = &mut state.groups[0];
+
+        assert_eq!(
+            vec!["Rust", "Web Dev"],
+            group.tags().map(|(_, tag)| tag).collect::<Vec<_>>()
+        );
+        assert_eq!(4, group.page_count);
+
+        group.show_listing(Some(1));
+        assert_eq!(3, group.page_count);
+        assert_eq!(3, group.item_count());
+        assert_eq!(
+            PathBuf::from("tag/web-dev/index.html"),
+            group.get_file_url(0).unwrap()
+        );
+        assert_eq!(
+            PathBuf::from("tag/web-dev/page/2/index.html"),
+            group.get_file_url(1).unwrap()
+        );
+        group.show_page(2);
+        assert_eq!("4", container_text(group));
+        assert_eq!("Web Dev", group.resolve_format(":tag", 1, ""));
+
+        group.show_listing(None);
+        assert_eq!(4, group.page_count);
+        assert_eq!(4, group.item_count());
+        assert_eq!("", group.resolve_format(":tag", 1, ""));
     }
 
-    fn get_file_url(&self, page_number: usize) -> Result<PathBuf, errors::PageError> {
-        match page_number {
-            0 => Ok(PathBuf::from(&self.file_path)),
-            _ => {
-                let page_number = (&page_number + 1).to_string();
-                let file_url = self.page_url_format.replace(":num", &page_number);
-                let file_stem = self.file_path.file_stem().unwrap().to_str().unwrap();
-                let file_path = if !file_stem.eq("index") {
-                    PathBuf::from(file_stem).join(file_url).join("index.html")
-                } else {
-                    PathBuf::from(file_url).join("index.html")
-                };
-                let cleaned_path = self.file_path.parent().unwrap().join(file_path).lexiclean();
-                match cleaned_path.components().next().unwrap() {
-                    Component::ParentDir => Err(errors::PageError {
-                        code: errors::PageErrorCode::ParentDir,
-                        relative_path: self.file_path.to_str().unwrap().to_string(),
-                        message: format!(
-                            "Pagination URL resolves outside of output directory: {:?}",
-                            cleaned_path
-                        ),
-                    }),
-                    _ => Ok(cleaned_path),
-                }
-            }
-        }
+    #[test]
+    fn test_conflicting_listings() {
+        let state = hydrated_state(
+            r#"
+            <section data-pagebreak="1" data-pagebreak-sorts="by-title=text"><p data-pagebreak-tags="rust">Post 1</p></section>
+            <section data-pagebreak="1" data-pagebreak-url="./events/:num/" data-pagebreak-sorts="by-title=text, by-date=data-date"><p data-pagebreak-tags="rust, talks">Event 1</p></section>
+            <section data-pagebreak="1" data-pagebreak-url="./talks/:num/" data-pagebreak-tag-url="./talks/:tag/"><p data-pagebreak-tags="rust">Talk 1</p></section>
+            "#,
+        );
+        let paths = |group: &PagebreakGroup| {
+            group
+                .listings
+                .iter()
+                .map(|listing| listing.path.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(vec!["by-title", "./tag/rust/"], paths(&state.groups[0]));
+        assert_eq!(vec!["by-date", "./tag/talks/"], paths(&state.groups[1]));
+        assert_eq!(vec!["./talks/rust/"], paths(&state.groups[2]));
     }
 
-    fn relative_path_between_pages(&self, from: usize, to: usize) -> String {
-        let from_path = self.get_file_url(from).unwrap();
-        let to_path = self.get_file_url(to).unwrap();
-        let mut relative_path =
-            pathdiff::diff_paths(to_path.parent().unwrap(), from_path.parent().unwrap()).unwrap();
-        // relative_path.strip_prefix(base)
-        if let Component::CurDir = relative_path.components().next().unwrap() {
-            relative_path = relative_path
-                .strip_prefix(".")
-                .expect("Prefix was checked")
-                .to_path_buf();
-        }
-        format!(
-            "{}/",
-            relative_path
-                .to_str()
-                .expect("valid characters")
-                .replace("\\", "/")
-        )
+    #[test]
+    fn test_archive_numbering() {
+        let state = hydrated_state(
+            r#"<section data-pagebreak="2" data-pagebreak-archive><p>5</p><p>4</p><p>3</p><p>2</p><p>1</p></section>"#,
+        );
+        let group = &state.groups[0];
+
+        assert_eq!(vec![0..1, 1..3, 3..5], group.containers[0].pages);
+        assert_eq!(3, group.page_number(0));
+        assert_eq!(1, group.page_number(2));
+        assert_eq!(PathBuf::from("index.html"), group.get_file_url(0).unwrap());
+        assert_eq!(
+            PathBuf::from("page/2/index.html"),
+            group.get_file_url(1).unwrap()
+        );
+        assert_eq!(
+            PathBuf::from("page/1/index.html"),
+            group.get_file_url(2).unwrap()
+        );
+        assert_eq!(
+            "Page 1 of 3",
+            group.resolve_format("Page :num of :total", 2, "")
+        );
     }
 
-    fn write_current_document_to_disk(&self, path: PathBuf) {
-        let mut file = std::io::BufWriter::new(std::fs::File::create(path).unwrap());
-        self.document.serialize(&mut file).unwrap();
+    #[test]
+    fn test_selected_items() {
+        let state = hydrated_state(
+            r#"<section data-pagebreak="2" data-pagebreak-items=".card"><h2>Title</h2><div class="row"><div class="card">1</div><div class="card">2</div></div><div class="row"><div class="card">3</div><div class="card">4 <span class="card">!</span></div></div><div class="row"><div class="card">5</div></div></section>"#,
+        );
+        let group = &state.groups[0];
+
+        assert_eq!(5, group.item_count());
+        assert_eq!(3, group.page_count);
+
+        let container = || group.containers[0].node.as_node().to_string();
+        group.show_page(1);
+        assert_eq!(
+            r#"<section><h2>Title</h2><div class="row"><div class="card">3</div><div class="card">4 <span class="card">!</span></div></div><div class="row"></div><div class="row"></div></section>"#,
+            container()
+        );
+        group.show_page(2);
+        assert_eq!(
+            r#"<section><h2>Title</h2><div class="row"><div class="card">5</div></div><div class="row"></div><div class="row"></div></section>"#,
+            container()
+        );
     }
-}
 
-pub trait PagebreakStatusLogging {
-    fn log_hydrated(&self);
-}
+    #[test]
+    fn test_multi_element_items() {
+        let state = hydrated_state(
+            r#"<dl data-pagebreak="2" data-pagebreak-item-start="dt"><dt>A</dt><dd>a</dd><dt>B</dt><dd>b</dd><dd>b</dd><dt>C</dt><dt>D</dt><dd>d</dd></dl>"#,
+        );
+        let group = &state.groups[0];
 
-impl PagebreakStatusLogging for PagebreakState {
-    fn log_hydrated(&self) {
-        println!(
-            "Pagebreak: Found {} items on {:?}; Building {} pages of size {}",
-            self.page_items.as_ref().unwrap().borrow().len(),
-            self.file_path,
-            self.page_count.unwrap(),
-            self.per_page.unwrap()
+        assert_eq!(4, group.item_count());
+        assert_eq!(2, group.page_count);
+        group.show_page(0);
+        assert_eq!("AaBbb", container_text(group));
+        group.show_page(1);
+        assert_eq!("CDd", container_text(group));
+    }
+
+    #[test]
+    fn test_item_start_markers() {
+        let state = hydrated_state(
+            r#"<section data-pagebreak="1"><h3 data-pagebreak-item-start>A</h3><p>a</p><h3 data-pagebreak-item-start>B</h3><p>b</p></section>"#,
         );
+        let group = &state.groups[0];
+
+        assert_eq!(2, group.page_count);
+        group.show_page(1);
+        assert_eq!("Bb", container_text(group));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use kuchiki::traits::TendrilSink;
+    #[test]
+    fn test_repeated_headings() {
+        let state = hydrated_state(
+            r#"<section data-pagebreak="2"><h2 data-pagebreak-heading=":content (continued)">March</h2><p>1</p><p>2</p><p>3</p><h2 data-pagebreak-heading>April</h2><p>4</p><p>5</p></section>"#,
+        );
+        let group = &state.groups[0];
 
-    use super::*;
+        assert_eq!(5, group.item_count());
+        assert_eq!(3, group.page_count);
+        group.show_page(0);
+        assert_eq!("March12", container_text(group));
+        group.show_page(1);
+        assert_eq!("March (continued)3April4", container_text(group));
+        group.show_page(2);
+        assert_eq!("April5", container_text(group));
+        group.show_page(0);
+        assert_eq!("March12", container_text(group));
+    }
 
-    fn new_state() -> PagebreakState {
-        PagebreakState::new(
-            kuchiki::parse_html().one(""),
-            PathBuf::from("index.html"),
-            PathBuf::from("output"),
-        )
+    #[test]
+    fn test_sorted_headings() {
+        let mut state = hydrated_state(
+            r#"<section data-pagebreak="2" data-pagebreak-sorts="by-price=data-price"><h2 data-pagebreak-heading=":content (continued)">March</h2><p data-price="3">1</p><p data-price="1">2</p><h2 data-pagebreak-heading>April</h2><p data-price="2">3</p><p data-price="4">4</p></section>"#,
+        );
+        let group = &mut state.groups[0];
+
+        group.show_listing(Some(0));
+        group.show_page(0);
+        assert_eq!("March2April3", container_text(group));
+        group.show_page(1);
+        assert_eq!("March1April4", container_text(group));
     }
 
     #[test]
-    fn test_get_file_url() {
-        let mut state = new_state();
-        assert_eq!(PathBuf::from("index.html"), state.get_file_url(0).unwrap(),);
+    fn test_tagged_headings() {
+        let mut state = hydrated_state(
+            r#"<section data-pagebreak="1"><h2 data-pagebreak-heading=":content (continued)">March</h2><p data-pagebreak-tags="rust">1</p><p>2</p><h2 data-pagebreak-heading=":content (continued)">April</h2><p>3</p><p data-pagebreak-tags="rust">4</p><p data-pagebreak-tags="rust">5</p></section>"#,
+        );
+        let group = &mut state.groups[0];
+
+        group.show_listing(Some(0));
+        group.show_page(0);
+        assert_eq!("March1", container_text(group));
+        group.show_page(1);
+        assert_eq!("April4", container_text(group));
+        group.show_page(2);
+        assert_eq!("April (continued)5", container_text(group));
+    }
+
+    #[test]
+    fn test_literal_heading_format() {
+        let state = hydrated_state(
+            r#"<section data-pagebreak="1"><h2 data-pagebreak-heading="Continued">March</h2><p>1</p><p>2</p></section>"#,
+        );
+        let group = &state.groups[0];
+
+        group.show_page(1);
+        assert_eq!("Continued2", container_text(group));
+        group.show_page(0);
+        assert_eq!("March1", container_text(group));
+    }
+
+    #[test]
+    fn test_page_break_markers() {
+        let state = hydrated_state(
+            r#"<section data-pagebreak><p>1</p><p>2</p><hr data-pagebreak-break><p>3</p></section><section data-pagebreak="2" data-pagebreak-url="./more/:num/"><p>1</p><hr data-pagebreak-break><p>2</p><p>3</p><p>4</p></section>"#,
+        );
+
+        assert_eq!(vec![0..2, 2..3], state.groups[0].containers[0].pages);
+        assert_eq!(vec![0..1, 1..3, 3..4], state.groups[1].containers[0].pages);
+
+        state.groups[0].show_page(0);
         assert_eq!(
-            PathBuf::from("page/2/index.html"),
-            state.get_file_url(1).unwrap(),
+            "<section>\n<p>1</p>\n<p>2</p>\n</section>",
+            state.groups[0].containers[0].node.as_node().to_string()
+        );
+    }
+
+    #[test]
+    fn test_article_sections() {
+        let state = hydrated_state(
+            r#"<article data-pagebreak-article="h2"><p>Intro</p><div class="prose"><p>A</p><h2>One</h2><p>B</p><section><h2>Two</h2><p>C</p></section></div></article>"#,
         );
+        let group = &state.groups[0];
+
+        assert_eq!(3, group.page_count);
 
-        state.file_path = PathBuf::from("about/index.html");
+        let container = || group.containers[0].node.as_node().to_string();
+        group.show_page(0);
         assert_eq!(
-            PathBuf::from("about/index.html"),
-            state.get_file_url(0).unwrap(),
+            r#"<article><p>Intro</p><div class="prose"><p>A</p></div></article>"#,
+            container()
         );
+        group.show_page(1);
         assert_eq!(
-            PathBuf::from("about/page/2/index.html"),
-            state.get_file_url(1).unwrap(),
+            r#"<article><div class="prose"><h2>One</h2><p>B</p></div></article>"#,
+            container()
+        );
+        group.show_page(2);
+        assert_eq!(
+            r#"<article><div class="prose"><section><h2>Two</h2><p>C</p></section></div></article>"#,
+            container()
         );
+    }
+
+    #[test]
+    fn test_article_markers() {
+        let state = hydrated_state("<article data-pagebreak-article>\n<p>A</p>\n<!-- more -->\n<p>B</p>\n<!-- more -->\n</article>");
+        let group = &state.groups[0];
 
-        state.file_path = PathBuf::from("a/b/c/index.html");
-        state.page_url_format = "../../page/:num/".to_string();
+        assert_eq!(2, group.page_count);
+        group.show_page(1);
         assert_eq!(
-            PathBuf::from("a/page/2/index.html"),
-            state.get_file_url(1).unwrap(),
+            "<article>\n<p>B</p>\n</article>",
+            group.containers[0].node.as_node().to_string()
         );
     }
 
     #[test]
-    fn test_bad_file_url() {
-        let mut state = new_state();
-        state.page_url_format = "../page/:num/".to_string();
+    fn test_nested_containers() {
+        let mut state = hydrated_state(
+            r#"<section data-pagebreak="1" data-pagebreak-url="./category/:num/"><div><ul data-pagebreak="1"><li>A1</li><li>A2</li></ul><a data-pagebreak-control="next">Next</a></div><div><ul data-pagebreak="1"><li>B1</li><li>B2</li><li>B3</li></ul></div></section><a data-pagebreak-control="next">Next</a>"#,
+        );
+
+        assert_eq!(1, state.groups.len());
+        assert_eq!(2, state.groups[0].page_count);
+        assert_eq!(1, state.pagebreak_elements.as_ref().unwrap().len());
+        assert_eq!(2, state.nested.len());
+
+        let nested = &mut state.nested[1].state;
+        assert_eq!(3, nested.groups[0].page_count);
+        assert_eq!(0, nested.pagebreak_elements.as_ref().unwrap().len());
+        nested.set_file_path(Path::new("category/2/index.html"));
         assert_eq!(
-            errors::PageErrorCode::ParentDir,
-            state.get_file_url(1).unwrap_err().code,
+            PathBuf::from("category/2/page/3/index.html"),
+            nested.groups[0].get_file_url(2).unwrap()
+        );
+        assert_eq!(
+            vec![
+                PathBuf::from("category/2/page/2/index.html"),
+                PathBuf::from("category/2/page/3/index.html")
+            ],
+            nested.series_urls()
+        );
+        assert_eq!(
+            vec![PathBuf::from("category/2/index.html")],
+            state.series_urls()
+        );
+        assert_eq!(
+            1,
+            state.nested[0]
+                .state
+                .pagebreak_elements
+                .as_ref()
+                .unwrap()
+                .len()
         );
     }
 
     #[test]
-    fn test_relative_pagination_urls() {
-        let mut state = new_state();
-        assert_eq!("page/2/", state.relative_path_between_pages(0, 1));
-        assert_eq!("../../", state.relative_path_between_pages(1, 0));
-        assert_eq!("../3/", state.relative_path_between_pages(1, 2));
-
-        state.file_path = PathBuf::from("file/main/index.html");
-        state.page_url_format = "../pages/:num/page/".to_string();
-        assert_eq!("../pages/2/page/", state.relative_path_between_pages(0, 1));
-        assert_eq!("../../../main/", state.relative_path_between_pages(1, 0));
-        assert_eq!("../../3/page/", state.relative_path_between_pages(1, 2));
-
-        state.file_path = PathBuf::from("index.html");
-        state.page_url_format = "./:num/".to_string();
-        assert_eq!("2/", state.relative_path_between_pages(0, 1));
-        assert_eq!("../", state.relative_path_between_pages(1, 0));
+    fn test_deeply_nested_containers() {
+        let state = hydrated_state(
+            r#"<section data-pagebreak="1"><div><ul data-pagebreak="1"><li><ol data-pagebreak="1" data-pagebreak-url="./deep/:num/"><li>1</li><li>2</li></ol></li></ul></div></section>"#,
+        );
+
+        assert_eq!(1, state.nested.len());
+        assert!(state.nested[0].state.nested.is_empty());
+        let mut html = vec![];
+        state.document.serialize(&mut html).unwrap();
+        assert!(!String::from_utf8(html).unwrap().contains("data-pagebreak"));
+    }
+
+    #[test]
+    fn test_empty_containers() {
+        let state = hydrated_state(
+            r#"<section data-pagebreak="2"></section><section data-pagebreak="2" data-pagebreak-url="./more/:num/"><p data-pagebreak-empty>Nothing</p></section>"#,
+        );
+
+        assert_eq!(2, state.groups.len());
+        let group = &state.groups[1];
+        assert_eq!(1, group.page_count);
+        assert_eq!(0, group.item_count());
+        assert_eq!((0, 0), group.item_range(0));
+        assert_eq!(
+            "Showing 0 of 0, page 1 of 1",
+            group.resolve_format("Showing :last of :items, page :num of :total", 0, "")
+        );
+
+        group.show_page(0);
+        assert_eq!(
+            "<section>\n<p>Nothing</p>\n</section>",
+            group.containers[0].node.as_node().to_string()
+        );
+    }
+
+    #[test]
+    fn test_placeholders_with_selected_items() {
+        let state = hydrated_state(
+            r#"<div data-pagebreak="2" data-pagebreak-items=".post"><p data-pagebreak-empty>Nothing</p><ul><li class="post">1</li></ul></div><div data-pagebreak="2" data-pagebreak-items=".post" data-pagebreak-url="./more/:num/"><p data-pagebreak-empty>Nothing</p><ul></ul></div><article data-pagebreak-article="h2" data-pagebreak-url="./article/:num/"><p data-pagebreak-empty>Nothing</p><p>Intro</p></article>"#,
+        );
+
+        state.groups[0].show_page(0);
+        assert_eq!("1", container_text(&state.groups[0]));
+        state.groups[1].show_page(0);
+        assert_eq!(
+            "<div><p>Nothing</p><ul></ul></div>",
+            state.groups[1].containers[0].node.as_node().to_string()
+        );
+        state.groups[2].show_page(0);
+        assert_eq!("Intro", container_text(&state.groups[2]));
+    }
+
+    #[test]
+    fn test_max_pages() {
+        let state = hydrated_state(
+            r#"<section data-pagebreak="2" data-pagebreak-max-pages="2"><p>1</p><p>2</p><p>3</p><p>4</p><p>5</p></section><section data-pagebreak="2" data-pagebreak-max-pages="2" data-pagebreak-archive data-pagebreak-url="./archive/:num/"><p>5</p><p>4</p><p>3</p><p>2</p><p>1</p></section><section data-pagebreak="2" data-pagebreak-max-pages="3" data-pagebreak-url="./more/:num/"><p>1</p><p>2</p></section>"#,
+        );
+
+        let group = &state.groups[0];
+        assert!(group.overflows());
+        assert_eq!(2, group.page_count);
+        assert_eq!(4, group.item_count());
+        assert_eq!(vec![0..2, 2..4], group.containers[0].pages);
+        group.show_page(1);
+        assert_eq!(
+            "<section>\n<p>3</p>\n<p>4</p>\n</section>",
+            group.containers[0].node.as_node().to_string()
+        );
+
+        // Archives keep every page, so that their page numbers never change
+        let archive = &state.groups[1];
+        assert!(!archive.overflows());
+        assert_eq!(None, archive.containers[0].max_pages);
+        assert_eq!(vec![0..1, 1..3, 3..5], archive.containers[0].pages);
+        assert_eq!(
+            PathBuf::from("archive/1/index.html"),
+            archive.get_file_url(2).unwrap()
+        );
+
+        assert!(!state.groups[2].overflows());
+        assert_eq!(1, state.groups[2].page_count);
+    }
+
+    #[test]
+    fn test_all_listing() {
+        let mut state = hydrated_state(
+            r#"<section data-pagebreak="2" data-pagebreak-max-pages="1" data-pagebreak-canonical="all"><p>1</p><p>2</p><p>3</p></section>"#,
+        );
+        let group = &mut state.groups[0];
+
+        assert_eq!(Some(0), group.all_listing());
+        assert_eq!(
+            PathBuf::from("all/index.html"),
+            group.get_listing_url(0).unwrap()
+        );
+        assert_eq!("all/", group.resolve_format(":rel-all", 0, ""));
+        assert_eq!(1, group.page_count);
+        assert!(group.overflows());
+
+        group.show_listing(Some(0));
+        assert_eq!(1, group.page_count);
+        assert_eq!(3, group.item_count());
+        assert!(!group.overflows());
+        group.show_page(0);
+        assert_eq!(
+            "<section>\n<p>1</p>\n<p>2</p>\n<p>3</p>\n</section>",
+            group.containers[0].node.as_node().to_string()
+        );
+
+        group.show_listing(None);
+        assert_eq!(2, group.item_count());
+    }
+
+    #[test]
+    fn test_conflicting_all_listings() {
+        let state = hydrated_state(
+            r#"
+            <link rel="canonical" href="https://example.com/">
+            <section data-pagebreak="1" data-pagebreak-all><p>Post 1</p><p>Post 2</p></section>
+            <section data-pagebreak="1" data-pagebreak-all data-pagebreak-canonical="all" data-pagebreak-url="./events/:num/"><p>Event 1</p><p>Event 2</p></section>
+            <section data-pagebreak="1" data-pagebreak-all="./all-talks/" data-pagebreak-url="./talks/:num/"><p>Talk 1</p><p>Talk 2</p></section>
+            "#,
+        );
+
+        assert_eq!(Some(0), state.groups[0].all_listing());
+        assert_eq!(None, state.groups[1].all_listing());
+        assert_eq!(
+            PathBuf::from("all-talks/index.html"),
+            state.groups[2].get_listing_url(0).unwrap()
+        );
+
+        state.groups[1].apply_changes(1);
+        assert_eq!(
+            r#"<link href="https://example.com/events/2/" rel="canonical">"#,
+            state
+                .document
+                .select_first("link")
+                .unwrap()
+                .as_node()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_grouped_containers() {
+        let state = hydrated_state(
+            r#"
+            <section id="hero" data-pagebreak="1" data-pagebreak-group="listing"><p>Hero 1</p><p>Hero 2</p></section>
+            <section id="grid" data-pagebreak="2" data-pagebreak-group="listing"><p>1</p><p>2</p><p>3</p><p>4</p><p>5</p></section>
+            <a href="" data-pagebreak-control="next" data-pagebreak-for="grid">Next</a>
+            "#,
+        );
+
+        assert_eq!(1, state.groups.len());
+        assert_eq!(2, state.groups[0].containers.len());
+        assert_eq!(3, state.groups[0].page_count);
+        assert_eq!(7, state.groups[0].item_count());
+        assert_eq!((1, 3), state.groups[0].item_range(0));
+        assert_eq!((4, 6), state.groups[0].item_range(1));
+        assert_eq!((7, 7), state.groups[0].item_range(2));
+        assert_eq!(Some(0), state.pagebreak_elements.as_ref().unwrap()[0].group);
     }
 }